@@ -0,0 +1,348 @@
+//! agenda views like `org-agenda-list`: the scheduled items, deadlines
+//! and timestamped events of one or more documents between two dates.
+
+use std::cmp::Ordering;
+
+use crate::{
+    headline::HeadlineGroup,
+    object::Document,
+    timestamp::{find_timestamps, Date, DelayKind, RangeEnd, Time, Timestamp},
+};
+
+/// the number of days before a deadline that it is shown today,
+/// unless the deadline has a warning period like `-3d`.
+/// see `org-deadline-warning-days`
+pub const DEADLINE_WARNING_DAYS: i64 = 14;
+
+/// the agenda entries between two dates
+#[derive(Debug)]
+pub struct Agenda<'t> {
+    pub from: Date,
+    pub to: Date,
+    /// sorted by date, time and priority
+    pub entries: Vec<AgendaEntry<'t>>,
+}
+
+/// an item shown on one day of the agenda
+#[derive(Debug, Clone)]
+pub struct AgendaEntry<'t> {
+    /// the day the entry is shown on
+    pub date: Date,
+    pub kind: EntryKind,
+    /// the time of day, if the timestamp has one
+    pub time: Option<Time>,
+    /// the timestamp the entry comes from
+    pub timestamp: Timestamp,
+    pub headline: &'t HeadlineGroup<'t>,
+    /// the index of the headline's document in the documents of the agenda
+    pub document: usize,
+    /// the effective priority of the headline in its document
+    pub priority: char,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    /// scheduled on the day
+    Scheduled,
+    /// scheduled `days` days before today and not done yet
+    ScheduledOverdue { days: i64 },
+    /// due on the day
+    Deadline,
+    /// due in `days` days, shown today during the warning period
+    DeadlineWarning { days: i64 },
+    /// due `days` days before today and not done yet
+    DeadlineOverdue { days: i64 },
+    /// an active timestamp in the title or text of a headline
+    Event,
+}
+
+impl<'t> Agenda<'t> {
+    /// the agenda of `documents` from `from` to `to` inclusive.
+    /// overdue items and deadline warnings are shown on `today`,
+    /// if it is in the range.
+    pub fn new(documents: &[&'t Document<'t>], from: Date, to: Date, today: Date) -> Self {
+        let mut entries = Vec::new();
+        for (index, document) in documents.iter().enumerate() {
+            let priorities = document.priorities();
+            for headline in document.headlines() {
+                if headline.headline.level() == 0 {
+                    continue;
+                }
+                let mut add = |date, kind, timestamp: Timestamp| {
+                    entries.push(AgendaEntry {
+                        date,
+                        kind,
+                        time: timestamp.time,
+                        timestamp,
+                        headline,
+                        document: index,
+                        priority: priorities.effective(headline.headline.priority()),
+                    })
+                };
+                let done = headline.headline.is_done(&document.status_labels);
+                let today = Some(today).filter(|today| (from..=to).contains(today) && !done);
+
+                if let Some(planning) = headline.planning() {
+                    if let Some(scheduled) = planning.scheduled {
+                        add_scheduled(scheduled, from, to, today, &mut add);
+                    }
+                    if let Some(deadline) = planning.deadline {
+                        add_deadline(deadline, from, to, today, &mut add);
+                    }
+                }
+
+                let text = headline
+                    .content()
+                    .flat_map(|content| content.inline_text())
+                    .chain(Some(headline.headline.title()));
+                for (_, timestamp) in text.flat_map(find_timestamps) {
+                    if timestamp.active {
+                        for date in event_dates(&timestamp, from, to) {
+                            add(date, EntryKind::Event, timestamp);
+                        }
+                    }
+                }
+            }
+        }
+
+        entries.sort_by(|a, b| {
+            a.date
+                .cmp(&b.date)
+                .then_with(|| compare_times(a.time, b.time))
+                .then_with(|| a.priority.cmp(&b.priority))
+        });
+        Agenda { from, to, entries }
+    }
+
+    /// the agenda of a single day
+    pub fn day(documents: &[&'t Document<'t>], date: Date, today: Date) -> Self {
+        Agenda::new(documents, date, date, today)
+    }
+
+    /// the agenda of the week from Monday to Sunday that contains `date`
+    pub fn week(documents: &[&'t Document<'t>], date: Date, today: Date) -> Self {
+        let monday = date.add_days(-i64::from(date.weekday()));
+        Agenda::new(documents, monday, monday.add_days(6), today)
+    }
+
+    /// the entries shown on `date`
+    pub fn entries_on(&self, date: Date) -> impl Iterator<Item = &AgendaEntry<'t>> {
+        self.entries.iter().filter(move |entry| entry.date == date)
+    }
+}
+
+/// add the days a scheduled item is shown on.
+/// a delay like `-2d` hides the item until 2 days after the scheduled day.
+/// `today` is `None` unless unfinished items should be carried over to it.
+fn add_scheduled<F: FnMut(Date, EntryKind, Timestamp)>(
+    scheduled: Timestamp,
+    from: Date,
+    to: Date,
+    today: Option<Date>,
+    add: &mut F,
+) {
+    let delay = |date: Date| match scheduled.delay {
+        Some(delay) if delay.kind == DelayKind::All || date == scheduled.date => {
+            delay.interval.days()
+        }
+        _ => 0,
+    };
+    let earliest = scheduled.delay.map_or(0, |delay| delay.interval.days());
+    for date in scheduled.occurrences(from.add_days(-earliest), to) {
+        let shown = date.add_days(delay(date));
+        if (from..=to).contains(&shown) {
+            add(shown, EntryKind::Scheduled, scheduled);
+        }
+    }
+
+    if let Some(today) = today {
+        let shown = scheduled.date.add_days(delay(scheduled.date));
+        let repeats_today = scheduled.occurrences(today, today).next().is_some();
+        if shown < today && !repeats_today {
+            let days = today.days() - scheduled.date.days();
+            add(today, EntryKind::ScheduledOverdue { days }, scheduled);
+        }
+    }
+}
+
+/// add the days a deadline is shown on, and a warning or an overdue entry today.
+/// `today` is `None` unless unfinished items should be shown on it.
+fn add_deadline<F: FnMut(Date, EntryKind, Timestamp)>(
+    deadline: Timestamp,
+    from: Date,
+    to: Date,
+    today: Option<Date>,
+    add: &mut F,
+) {
+    for date in deadline.occurrences(from, to) {
+        add(date, EntryKind::Deadline, deadline);
+    }
+
+    let today = match today {
+        Some(today) => today,
+        None => return,
+    };
+    if deadline.date < today {
+        if deadline.occurrences(today, today).next().is_none() {
+            let days = today.days() - deadline.date.days();
+            add(today, EntryKind::DeadlineOverdue { days }, deadline);
+        }
+        return;
+    }
+
+    let warning_days = |date: Date| match deadline.delay {
+        Some(delay) if delay.kind == DelayKind::All || date == deadline.date => {
+            delay.interval.days()
+        }
+        Some(_) => 0,
+        None => DEADLINE_WARNING_DAYS,
+    };
+    let longest = deadline.delay.map_or(DEADLINE_WARNING_DAYS, |delay| {
+        delay.interval.days().max(DEADLINE_WARNING_DAYS)
+    });
+    let next = deadline
+        .occurrences(today.add_days(1), today.add_days(longest))
+        .next();
+    if let Some(next) = next {
+        let days = next.days() - today.days();
+        if days <= warning_days(next) {
+            add(today, EntryKind::DeadlineWarning { days }, deadline);
+        }
+    }
+}
+
+/// the days between `from` and `to` that an event is on.
+/// date ranges like `<2020-02-28 Fri>--<2020-03-01 Sun>` are on every day they span.
+fn event_dates(timestamp: &Timestamp, from: Date, to: Date) -> Vec<Date> {
+    match timestamp.end {
+        Some(RangeEnd::Date(end, _)) => {
+            let start = timestamp.date.max(from);
+            let end = end.min(to);
+            (start.days()..=end.days()).map(Date::from_days).collect()
+        }
+        _ => timestamp.occurrences(from, to).collect(),
+    }
+}
+
+/// entries with a time come first, like in `org-agenda`
+fn compare_times(a: Option<Time>, b: Option<Time>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_org_text;
+
+    const TEST_STATES: [&str; 3] = ["TODO", "STARTED", "DONE"];
+
+    const AGENDA_TEXT: &str = "* TODO [#A] review
+SCHEDULED: <2020-02-26 Wed>
+* TODO report
+DEADLINE: <2020-03-05 Thu>
+* TODO taxes
+DEADLINE: <2020-02-20 Thu -3d>
+* TODO standup
+SCHEDULED: <2020-02-24 Mon 09:30 +1d>
+* DONE cleanup
+SCHEDULED: <2020-02-25 Tue>
+* TODO later
+SCHEDULED: <2020-02-25 Tue -2d>
+* meeting <2020-02-28 Fri 14:00-15:00>
+* conference
+<2020-02-29 Sat>--<2020-03-01 Sun>
+written [2020-02-27 Thu]
+";
+
+    fn day(s: &str) -> Date {
+        Date::parse(s).unwrap()
+    }
+
+    fn summary<'a>(agenda: &'a Agenda<'_>, date: &str) -> Vec<(&'a str, usize, EntryKind)> {
+        agenda
+            .entries_on(day(date))
+            .map(|entry| (entry.headline.headline.title(), entry.document, entry.kind))
+            .collect()
+    }
+
+    #[test]
+    fn week_agenda() {
+        let document = parse_org_text(AGENDA_TEXT, TEST_STATES.to_vec()).unwrap();
+        let other = parse_org_text(
+            "* TODO [#A] call\nSCHEDULED: <2020-02-28 Fri>\n",
+            TEST_STATES.to_vec(),
+        )
+        .unwrap();
+        let today = day("2020-02-28");
+        let agenda = Agenda::week(&[&document, &other], today, today);
+        assert_eq!(
+            (agenda.from, agenda.to),
+            (day("2020-02-24"), day("2020-03-01"))
+        );
+
+        use EntryKind::*;
+        assert_eq!(summary(&agenda, "2020-02-24"), [("standup", 0, Scheduled)]);
+        assert_eq!(
+            summary(&agenda, "2020-02-25"),
+            [("standup", 0, Scheduled), ("cleanup", 0, Scheduled)]
+        );
+        assert_eq!(
+            summary(&agenda, "2020-02-26"),
+            [("standup", 0, Scheduled), ("review", 0, Scheduled)]
+        );
+        assert_eq!(
+            summary(&agenda, "2020-02-27"),
+            [("standup", 0, Scheduled), ("later", 0, Scheduled)]
+        );
+        assert_eq!(
+            summary(&agenda, "2020-02-28"),
+            [
+                ("standup", 0, Scheduled),
+                ("meeting <2020-02-28 Fri 14:00-15:00>", 0, Event),
+                ("review", 0, ScheduledOverdue { days: 2 }),
+                ("call", 1, Scheduled),
+                ("report", 0, DeadlineWarning { days: 6 }),
+                ("taxes", 0, DeadlineOverdue { days: 8 }),
+                ("later", 0, ScheduledOverdue { days: 3 }),
+            ]
+        );
+        assert_eq!(
+            summary(&agenda, "2020-03-01"),
+            [("standup", 0, Scheduled), ("conference", 0, Event)]
+        );
+
+        let first = &agenda.entries[0];
+        assert_eq!(first.time, Time::parse("09:30"));
+        assert_eq!(first.priority, 'B');
+    }
+
+    #[test]
+    fn day_agenda() {
+        let document = parse_org_text(AGENDA_TEXT, TEST_STATES.to_vec()).unwrap();
+        let today = day("2020-02-28");
+
+        // overdue items and warnings are only shown on today
+        let agenda = Agenda::day(&[&document], day("2020-02-27"), today);
+        assert_eq!(
+            summary(&agenda, "2020-02-27"),
+            [
+                ("standup", 0, EntryKind::Scheduled),
+                ("later", 0, EntryKind::Scheduled)
+            ]
+        );
+
+        let agenda = Agenda::day(&[&document], day("2020-03-05"), day("2020-03-05"));
+        let deadlines: Vec<_> = agenda
+            .entries
+            .iter()
+            .filter(|entry| entry.kind == EntryKind::Deadline)
+            .map(|entry| entry.headline.headline.title())
+            .collect();
+        assert_eq!(deadlines, ["report"]);
+    }
+}
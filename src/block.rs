@@ -0,0 +1,251 @@
+use std::{fmt, fmt::Display};
+
+const BEGIN: &str = "#+BEGIN_";
+const END: &str = "#+END_";
+
+/// a `#+BEGIN_NAME` ... `#+END_NAME` block.
+/// the body is kept verbatim.
+#[derive(Debug)]
+pub struct Block<'t> {
+    /// the name as written, e.g. `src` or `SRC`
+    pub name: &'t str,
+    pub kind: BlockKind<'t>,
+    /// the language of a source block or the backend of an export block
+    pub language: Option<&'t str>,
+    /// switches like `-n` or `-l "(ref:%s)"`
+    pub switches: Vec<&'t str>,
+    /// header arguments like `:results output`
+    pub header_args: Vec<(&'t str, &'t str)>,
+    pub body: Vec<&'t str>,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum BlockKind<'t> {
+    Src,
+    Quote,
+    Example,
+    Verse,
+    Center,
+    Export,
+    Comment,
+    /// any other block, e.g. `#+BEGIN_NOTE`
+    Special(&'t str),
+}
+
+impl<'t> BlockKind<'t> {
+    pub fn from_name(name: &'t str) -> Self {
+        match name.to_ascii_uppercase().as_str() {
+            "SRC" => BlockKind::Src,
+            "QUOTE" => BlockKind::Quote,
+            "EXAMPLE" => BlockKind::Example,
+            "VERSE" => BlockKind::Verse,
+            "CENTER" => BlockKind::Center,
+            "EXPORT" => BlockKind::Export,
+            "COMMENT" => BlockKind::Comment,
+            _ => BlockKind::Special(name),
+        }
+    }
+}
+
+impl<'t> Block<'t> {
+    /// parse a `#+BEGIN_NAME parameters` line into a block with an empty body
+    pub fn parse_begin(line: &'t str) -> Option<Block<'t>> {
+        let line = line.trim();
+        let rem = strip_prefix_ignore_case(line, BEGIN)?;
+        let name_end = rem.find(char::is_whitespace).unwrap_or(rem.len());
+        let (name, parameters) = rem.split_at(name_end);
+        if name.is_empty() {
+            return None;
+        }
+
+        let kind = BlockKind::from_name(name);
+        let mut tokens = tokenize(parameters).into_iter().peekable();
+
+        let language = match kind {
+            BlockKind::Src | BlockKind::Export => {
+                tokens.next_if(|token| !token.starts_with(&['-', '+', ':'][..]))
+            }
+            _ => None,
+        };
+
+        let mut switches = Vec::new();
+        while let Some(switch) = tokens.next_if(|token| token.starts_with(&['-', '+'][..])) {
+            switches.push(switch);
+            // `-l` takes a quoted format argument
+            if let Some(argument) = tokens.next_if(|token| token.starts_with('"')) {
+                switches.push(argument);
+            }
+        }
+
+        let rest: Vec<&'t str> = tokens.collect();
+        let header_args = parse_header_args(parameters, &rest);
+
+        Some(Block {
+            name,
+            kind,
+            language,
+            switches,
+            header_args,
+            body: Vec::new(),
+        })
+    }
+
+    /// check if `line` is the end of a block named `name`
+    pub fn is_end(line: &str, name: &str) -> bool {
+        strip_prefix_ignore_case(line.trim(), END)
+            .map(|end_name| end_name.eq_ignore_ascii_case(name))
+            .unwrap_or(false)
+    }
+
+    /// get the value of a header argument, e.g. `:results`
+    pub fn header_arg(&self, key: &str) -> Option<&'t str> {
+        self.header_args
+            .iter()
+            .rev()
+            .find(|(arg, _)| arg.eq_ignore_ascii_case(key))
+            .map(|(_, value)| *value)
+    }
+
+    fn is_lowercase(&self) -> bool {
+        !self.name.chars().any(|ch| ch.is_ascii_uppercase())
+    }
+}
+
+pub fn strip_prefix_ignore_case<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    match s.get(..prefix.len()) {
+        Some(start) if start.eq_ignore_ascii_case(prefix) => Some(&s[prefix.len()..]),
+        _ => None,
+    }
+}
+
+/// split on whitespace, keeping quoted strings together
+pub fn tokenize(s: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut in_quotes = false;
+    for (i, ch) in s.char_indices() {
+        match (start, ch) {
+            (_, '"') => {
+                in_quotes = !in_quotes;
+                start.get_or_insert(i);
+            }
+            (Some(token_start), ch) if ch.is_whitespace() && !in_quotes => {
+                tokens.push(&s[token_start..i]);
+                start = None;
+            }
+            (None, ch) if !ch.is_whitespace() => start = Some(i),
+            _ => {}
+        }
+    }
+    if let Some(token_start) = start {
+        tokens.push(&s[token_start..]);
+    }
+    tokens
+}
+
+/// parse `:key value` pairs from `tokens` of `parameters`.
+/// values can span several tokens.
+pub fn parse_header_args<'t>(parameters: &'t str, tokens: &[&'t str]) -> Vec<(&'t str, &'t str)> {
+    let mut header_args = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let key = tokens[i];
+        let value_start = i + 1;
+        let mut value_end = value_start;
+        while value_end < tokens.len() && !tokens[value_end].starts_with(':') {
+            value_end += 1;
+        }
+        let value = if value_start < value_end {
+            span(parameters, tokens[value_start], tokens[value_end - 1])
+        } else {
+            ""
+        };
+        header_args.push((key, value));
+        i = value_end;
+    }
+    header_args
+}
+
+/// the slice of `s` from the start of `first` to the end of `last`,
+/// where both are slices of `s`
+fn span<'a>(s: &'a str, first: &str, last: &str) -> &'a str {
+    let start = first.as_ptr() as usize - s.as_ptr() as usize;
+    let end = last.as_ptr() as usize - s.as_ptr() as usize + last.len();
+    &s[start..end]
+}
+
+impl<'t> Display for Block<'t> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (begin, end) = if self.is_lowercase() {
+            ("#+begin_", "#+end_")
+        } else {
+            (BEGIN, END)
+        };
+
+        write!(f, "{}{}", begin, self.name)?;
+        if let Some(language) = self.language {
+            write!(f, " {}", language)?;
+        }
+        for switch in &self.switches {
+            write!(f, " {}", switch)?;
+        }
+        for (key, value) in &self.header_args {
+            write!(f, " {}", key)?;
+            if !value.is_empty() {
+                write!(f, " {}", value)?;
+            }
+        }
+        writeln!(f)?;
+
+        for line in &self.body {
+            writeln!(f, "{}", line)?;
+        }
+        writeln!(f, "{}{}", end, self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_src_parameters() {
+        let block = Block::parse_begin(
+            r#"#+BEGIN_SRC rust -n -l "(ref:%s)" :results output silent :exports both"#,
+        )
+        .unwrap();
+
+        assert_eq!(block.kind, BlockKind::Src);
+        assert_eq!(block.language, Some("rust"));
+        assert_eq!(block.switches, vec!["-n", "-l", r#""(ref:%s)""#]);
+        assert_eq!(
+            block.header_args,
+            vec![(":results", "output silent"), (":exports", "both")]
+        );
+        assert_eq!(block.header_arg(":RESULTS"), Some("output silent"));
+        assert_eq!(
+            format!("{}", block),
+            "#+BEGIN_SRC rust -n -l \"(ref:%s)\" :results output silent :exports both\n#+END_SRC\n"
+        );
+    }
+
+    #[test]
+    fn parse_block_kinds() {
+        let block = Block::parse_begin("#+begin_export html").unwrap();
+        assert_eq!(block.kind, BlockKind::Export);
+        assert_eq!(block.language, Some("html"));
+        assert_eq!(format!("{}", block), "#+begin_export html\n#+end_export\n");
+
+        let block = Block::parse_begin("  #+BEGIN_QUOTE").unwrap();
+        assert_eq!(block.kind, BlockKind::Quote);
+        assert_eq!(block.language, None);
+
+        let block = Block::parse_begin("#+BEGIN_NOTE").unwrap();
+        assert_eq!(block.kind, BlockKind::Special("NOTE"));
+
+        assert!(Block::parse_begin("#+BEGIN_").is_none());
+        assert!(Block::parse_begin("#+BEGIN: clocktable").is_none());
+        assert!(Block::is_end("#+end_src ", "SRC"));
+        assert!(!Block::is_end("#+END_SRC", "QUOTE"));
+    }
+}
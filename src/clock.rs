@@ -0,0 +1,216 @@
+use std::{
+    fmt,
+    fmt::Display,
+    ops::{Bound, RangeBounds},
+};
+
+use crate::timestamp::{Date, RangeEnd, Time, Timestamp};
+
+pub const CLOCK: &str = "CLOCK:";
+
+/// a clock line, usually in a `:LOGBOOK:` drawer, e.g.
+/// `CLOCK: [2020-02-28 Fri 10:00]--[2020-02-28 Fri 11:30] =>  1:30`.
+/// open clocks only have a start, `CLOCK: [2020-02-28 Fri 10:00]`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Clock<'t> {
+    pub start: (Date, Time),
+    pub end: Option<(Date, Time)>,
+    /// the duration after `=>` in minutes, as written in the text
+    pub duration: Option<i64>,
+    /// the text after `=>` as it was written, e.g. ` 1d 2:00`
+    pub duration_text: Option<&'t str>,
+    /// the whitespace before `CLOCK:` as it was written
+    pub indent: &'t str,
+}
+
+impl<'t> Clock<'t> {
+    pub fn parse(line: &'t str) -> Option<Clock<'t>> {
+        let rem = line.trim().strip_prefix(CLOCK)?.trim_start();
+        let (timestamp, rem) = Timestamp::parse_prefix(rem)?;
+        if timestamp.active || timestamp.repeater.is_some() || timestamp.delay.is_some() {
+            return None;
+        }
+
+        let start = (timestamp.date, timestamp.time?);
+        let end = match timestamp.end {
+            Some(RangeEnd::Date(date, time)) => Some((date, time?)),
+            Some(RangeEnd::Time(_)) => return None,
+            None => None,
+        };
+
+        let rem = rem.trim();
+        let duration_text = match rem.strip_prefix("=>") {
+            Some(duration) if end.is_some() => Some(duration),
+            Some(_) => return None,
+            None if rem.is_empty() => None,
+            None => return None,
+        };
+        let duration = match duration_text {
+            Some(duration) => Some(parse_duration(duration.trim())?),
+            None => None,
+        };
+
+        Some(Clock {
+            start,
+            end,
+            duration,
+            duration_text,
+            indent: &line[..line.len() - line.trim_start().len()],
+        })
+    }
+
+    /// check if the clock is still running
+    pub fn is_open(&self) -> bool {
+        self.end.is_none()
+    }
+
+    /// the clocked time in minutes computed from the timestamps,
+    /// or `None` if the clock is still running
+    pub fn minutes(&self) -> Option<i64> {
+        Some(minutes(self.end?) - minutes(self.start))
+    }
+
+    /// check the duration after `=>` against the timestamps.
+    /// returns `None` if there is nothing to check
+    pub fn duration_matches(&self) -> Option<bool> {
+        Some(self.duration? == self.minutes()?)
+    }
+
+    /// the minutes of the clock that fall between the start of the first day
+    /// and the end of the last day of `range`.
+    /// open clocks and clocks that end before they start count as 0.
+    pub fn minutes_in<R: RangeBounds<Date>>(&self, range: R) -> i64 {
+        let end = match self.end {
+            Some(end) => minutes(end),
+            None => return 0,
+        };
+        let start = minutes(self.start);
+
+        let from = match range.start_bound() {
+            Bound::Included(date) => start.max(date.days() * MINUTES_PER_DAY),
+            Bound::Excluded(date) => start.max((date.days() + 1) * MINUTES_PER_DAY),
+            Bound::Unbounded => start,
+        };
+        let to = match range.end_bound() {
+            Bound::Included(date) => end.min((date.days() + 1) * MINUTES_PER_DAY),
+            Bound::Excluded(date) => end.min(date.days() * MINUTES_PER_DAY),
+            Bound::Unbounded => end,
+        };
+        (to - from).max(0)
+    }
+}
+
+const MINUTES_PER_DAY: i64 = 24 * 60;
+
+fn minutes((date, time): (Date, Time)) -> i64 {
+    date.days() * MINUTES_PER_DAY + i64::from(time.minutes())
+}
+
+/// parse a clock duration like `1:30` or `2d 3:05`
+pub fn parse_duration(s: &str) -> Option<i64> {
+    let (days, time) = match s.split_once(char::is_whitespace) {
+        Some((days, time)) => (days.strip_suffix('d')?.parse().ok()?, time.trim()),
+        None => (0, s),
+    };
+    let (hours, minutes) = time.split_once(':')?;
+    let hours: i64 = hours.parse().ok()?;
+    let minutes: i64 = minutes.parse().ok()?;
+    if minutes >= 60 {
+        return None;
+    }
+    Some(days * MINUTES_PER_DAY + hours * 60 + minutes)
+}
+
+/// format minutes as a clock duration, e.g. `1:30`
+pub fn format_duration(minutes: i64) -> String {
+    let sign = if minutes < 0 { "-" } else { "" };
+    let minutes = minutes.abs();
+    format!("{}{}:{:02}", sign, minutes / 60, minutes % 60)
+}
+
+impl<'t> Display for Clock<'t> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (date, time) = self.start;
+        write!(f, "{}{} [{} {}]", self.indent, CLOCK, date, time)?;
+        if let Some((date, time)) = self.end {
+            write!(f, "--[{} {}]", date, time)?;
+        }
+        if let Some(duration) = self.duration {
+            // print the duration as written unless it was changed
+            match self.duration_text {
+                Some(text) if parse_duration(text.trim()) == Some(duration) => {
+                    write!(f, " =>{}", text)?
+                }
+                _ => write!(f, " => {:>5}", format_duration(duration))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(date: &str, time: &str) -> (Date, Time) {
+        (Date::parse(date).unwrap(), Time::parse(time).unwrap())
+    }
+
+    #[test]
+    fn parse_clocks() {
+        let line = "CLOCK: [2020-02-28 Fri 10:00]--[2020-02-28 Fri 11:30] =>  1:30";
+        let clock = Clock::parse(line).unwrap();
+        assert_eq!(clock.start, at("2020-02-28", "10:00"));
+        assert_eq!(clock.end, Some(at("2020-02-28", "11:30")));
+        assert_eq!(clock.duration, Some(90));
+        assert_eq!(clock.minutes(), Some(90));
+        assert_eq!(clock.duration_matches(), Some(true));
+        assert_eq!(format!("{}", clock), line);
+
+        let clock = Clock::parse("  CLOCK: [2020-02-28 Fri 23:00]").unwrap();
+        assert!(clock.is_open());
+        assert_eq!(clock.minutes(), None);
+        assert_eq!(clock.indent, "  ");
+        assert_eq!(format!("{}", clock), "  CLOCK: [2020-02-28 Fri 23:00]");
+
+        let line = "\tCLOCK: [2020-02-28 Fri 10:00]--[2020-02-28 Fri 11:00] => 1:00";
+        assert_eq!(format!("{}", Clock::parse(line).unwrap()), line);
+
+        let clock =
+            Clock::parse("CLOCK: [2020-02-28 Fri 23:00]--[2020-03-01 Sun 01:00] => 1d 2:00")
+                .unwrap();
+        assert_eq!(clock.minutes(), Some(26 * 60));
+        assert_eq!(clock.duration_matches(), Some(true));
+        assert_eq!(
+            format!("{}", clock),
+            "CLOCK: [2020-02-28 Fri 23:00]--[2020-03-01 Sun 01:00] => 1d 2:00"
+        );
+        let clock = Clock {
+            duration: Some(27 * 60),
+            ..clock
+        };
+        assert_eq!(
+            format!("{}", clock),
+            "CLOCK: [2020-02-28 Fri 23:00]--[2020-03-01 Sun 01:00] => 27:00"
+        );
+
+        let clock = Clock::parse("CLOCK: [2020-02-28 Fri 10:00]--[2020-02-28 Fri 11:30] => 1:23");
+        assert_eq!(clock.unwrap().duration_matches(), Some(false));
+
+        assert_eq!(Clock::parse("CLOCK: <2020-02-28 Fri 10:00>"), None);
+        assert_eq!(Clock::parse("CLOCK: [2020-02-28 Fri]"), None);
+        assert_eq!(Clock::parse("CLOCK: [2020-02-28 Fri 10:00] => 1:00"), None);
+        assert_eq!(Clock::parse("CLOCK: [2020-02-28 Fri 10:00] and text"), None);
+    }
+
+    #[test]
+    fn clock_minutes_in_range() {
+        let clock = Clock::parse("CLOCK: [2020-02-28 Fri 23:00]--[2020-02-29 Sat 01:30]").unwrap();
+        let day = |s| Date::parse(s).unwrap();
+
+        assert_eq!(clock.minutes_in(..), 150);
+        assert_eq!(clock.minutes_in(day("2020-02-28")..=day("2020-02-28")), 60);
+        assert_eq!(clock.minutes_in(day("2020-02-29")..), 90);
+        assert_eq!(clock.minutes_in(..day("2020-02-28")), 0);
+    }
+}
@@ -0,0 +1,431 @@
+//! clock reports like `org-clock-report`, e.g.
+//! ```text
+//! | Headline     | Time   |      |
+//! |--------------+--------+------|
+//! | *Total time* | *3:30* |      |
+//! |--------------+--------+------|
+//! | project      | 3:30   |      |
+//! | \_  task     |        | 2:00 |
+//! ```
+
+use std::{
+    borrow::Cow,
+    fmt,
+    fmt::Display,
+    ops::{Bound, RangeBounds},
+};
+
+use crate::{
+    clock::format_duration,
+    dynamic_block::{BlockWriter, DynamicBlock},
+    error::{OrgError, Result},
+    headline::HeadlineGroup,
+    object::Document,
+    table::{Row, Table},
+    timestamp::{Date, Timestamp},
+};
+
+/// the days a clock report covers,
+/// from the start of the first day to the end of the last day
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    from: Option<Date>,
+    to: Option<Date>,
+}
+
+/// a report of the time clocked on the headlines of one or more documents
+#[derive(Debug)]
+pub struct ClockReport<'t> {
+    pub span: Span,
+    /// the deepest headline level in the report
+    pub max_level: usize,
+    pub files: Vec<FileReport<'t>>,
+}
+
+/// the part of a clock report for one document
+#[derive(Debug)]
+pub struct FileReport<'t> {
+    /// the name of the file in reports over several files
+    pub name: Option<&'t str>,
+    /// the minutes clocked in the file
+    pub minutes: i64,
+    pub rows: Vec<ReportRow<'t>>,
+}
+
+/// writes clock reports into `#+BEGIN: clocktable` blocks.
+/// supports the `:maxlevel`, `:block`, `:tstart`, `:tend`
+/// and `:scope file` parameters.
+#[derive(Debug, Clone, Copy)]
+pub struct ClockTableWriter {
+    /// the date that `:block` parameters like `today` are relative to
+    pub today: Date,
+}
+
+/// a headline with clocked time in a clock report
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportRow<'t> {
+    pub level: usize,
+    /// the titles of the headline and its parents, starting from the top level
+    pub path: Vec<&'t str>,
+    /// the minutes clocked on the headline and its sub headlines
+    pub minutes: i64,
+}
+
+impl Span {
+    /// a span from the start of `from` to the end of `to`.
+    /// `None` leaves that side of the span open.
+    pub fn new(from: Option<Date>, to: Option<Date>) -> Span {
+        Span { from, to }
+    }
+
+    /// a span without limits
+    pub fn all() -> Span {
+        Span::new(None, None)
+    }
+
+    pub fn day(date: Date) -> Span {
+        Span::new(Some(date), Some(date))
+    }
+
+    /// the week from Monday to Sunday that contains `date`
+    pub fn week(date: Date) -> Span {
+        let monday = date.add_days(-i64::from(date.weekday()));
+        Span::new(Some(monday), Some(monday.add_days(6)))
+    }
+
+    /// the month that contains `date`
+    pub fn month(date: Date) -> Span {
+        let first = date.add_days(1 - i64::from(date.day()));
+        let last = first.add_months(1).add_days(-1);
+        Span::new(Some(first), Some(last))
+    }
+
+    /// the year that contains `date`
+    pub fn year(date: Date) -> Span {
+        Span::new(Date::new(date.year(), 1, 1), Date::new(date.year(), 12, 31))
+    }
+
+    /// parse the `:block` parameter of a clock table,
+    /// e.g. `today`, `lastweek` or `2020-02-28`, relative to `today`
+    pub fn parse_block(block: &str, today: Date) -> Option<Span> {
+        let span = match block {
+            "today" => Span::day(today),
+            "yesterday" => Span::day(today.add_days(-1)),
+            "thisweek" => Span::week(today),
+            "lastweek" => Span::week(today.add_days(-7)),
+            "thismonth" => Span::month(today),
+            "lastmonth" => Span::month(today.add_months(-1)),
+            "thisyear" => Span::year(today),
+            "lastyear" => Span::year(today.add_months(-12)),
+            date => Span::day(Date::parse(date)?),
+        };
+        Some(span)
+    }
+
+    pub fn from(&self) -> Option<Date> {
+        self.from
+    }
+
+    pub fn to(&self) -> Option<Date> {
+        self.to
+    }
+}
+
+impl RangeBounds<Date> for Span {
+    fn start_bound(&self) -> Bound<&Date> {
+        self.from.as_ref().map_or(Bound::Unbounded, Bound::Included)
+    }
+
+    fn end_bound(&self) -> Bound<&Date> {
+        self.to.as_ref().map_or(Bound::Unbounded, Bound::Included)
+    }
+}
+
+impl<'t> ClockReport<'t> {
+    /// a report of the time clocked in `document` during `span`
+    /// on headlines up to `max_level`
+    pub fn new(document: &'t Document<'t>, span: Span, max_level: usize) -> Self {
+        ClockReport {
+            span,
+            max_level,
+            files: vec![FileReport::new(None, document, span, max_level)],
+        }
+    }
+
+    /// a report over several documents and their file names
+    pub fn for_files(files: &[(&'t str, &'t Document<'t>)], span: Span, max_level: usize) -> Self {
+        let files = files
+            .iter()
+            .map(|(name, document)| FileReport::new(Some(name), document, span, max_level))
+            .collect();
+        ClockReport {
+            span,
+            max_level,
+            files,
+        }
+    }
+
+    /// the minutes clocked in all files
+    pub fn minutes(&self) -> i64 {
+        self.files.iter().map(|file| file.minutes).sum()
+    }
+
+    /// the report as an org table, like the body of a
+    /// `#+BEGIN: clocktable` dynamic block
+    pub fn table(&self) -> Table<'t> {
+        let levels = self
+            .files
+            .iter()
+            .flat_map(|file| file.rows.iter().map(|row| row.level))
+            .max()
+            .unwrap_or(1);
+        let with_files = self.files.iter().any(|file| file.name.is_some());
+
+        let row = |file: Cow<'t, str>, headline: Cow<'t, str>, level, time: Cow<'t, str>| {
+            let mut cells = Vec::new();
+            if with_files {
+                cells.push(file);
+            }
+            cells.push(headline);
+            cells.extend((1..=levels).map(|column| {
+                if column == level {
+                    time.clone()
+                } else {
+                    Cow::Borrowed("")
+                }
+            }));
+            Row::Cells(cells)
+        };
+
+        let mut table = Table::new();
+        table
+            .rows
+            .push(row("File".into(), "Headline".into(), 1, "Time".into()));
+        table.rows.push(Row::Rule);
+        let total_label = if with_files {
+            "ALL *Total time*"
+        } else {
+            "*Total time*"
+        };
+        let total = format!("*{}*", format_duration(self.minutes()));
+        table
+            .rows
+            .push(row("".into(), total_label.into(), 1, total.into()));
+
+        for file in &self.files {
+            table.rows.push(Row::Rule);
+            if let Some(name) = file.name {
+                let time = format!("*{}*", format_duration(file.minutes));
+                table
+                    .rows
+                    .push(row(name.into(), "*File time*".into(), 1, time.into()));
+            }
+            for report_row in &file.rows {
+                let title = escape_cell(report_row.path.last().copied().unwrap_or(""));
+                let headline = match report_row.level {
+                    1 => title,
+                    level => format!("\\_{}{}", " ".repeat(2 * (level - 1)), title).into(),
+                };
+                let time = format_duration(report_row.minutes).into();
+                table
+                    .rows
+                    .push(row("".into(), headline, report_row.level, time));
+            }
+        }
+        table
+    }
+}
+
+impl BlockWriter for ClockTableWriter {
+    fn write(&self, block: &DynamicBlock<'_>, document: &Document<'_>) -> Result<String> {
+        let invalid = |key: &str, value: &str| {
+            OrgError::ParseError(None, format!("invalid `{}` `{}`", key, value))
+        };
+
+        let max_level = match block.parameter(":maxlevel") {
+            Some(value) => value.parse().map_err(|_| invalid(":maxlevel", value))?,
+            None => 3,
+        };
+        if let Some(scope) = block.parameter(":scope").filter(|scope| *scope != "file") {
+            return Err(OrgError::ParseError(
+                None,
+                format!("unsupported `:scope` `{}`", scope),
+            ));
+        }
+
+        let span = match block.parameter(":block") {
+            Some(value) => {
+                Span::parse_block(value, self.today).ok_or_else(|| invalid(":block", value))?
+            }
+            None => {
+                let date = |key| match block.parameter(key) {
+                    Some(value) => Timestamp::parse(value.trim_matches('"'))
+                        .map(|timestamp| Some(timestamp.date))
+                        .ok_or_else(|| invalid(key, value)),
+                    None => Ok(None),
+                };
+                // `:tend` is the first day that is not included
+                let to = date(":tend")?.map(|date| date.add_days(-1));
+                Span::new(date(":tstart")?, to)
+            }
+        };
+
+        Ok(ClockReport::new(document, span, max_level).to_string())
+    }
+}
+
+impl<'t> FileReport<'t> {
+    fn new(
+        name: Option<&'t str>,
+        document: &'t Document<'t>,
+        span: Span,
+        max_level: usize,
+    ) -> Self {
+        let mut rows = Vec::new();
+        collect_rows(&document.root, &mut Vec::new(), span, max_level, &mut rows);
+        // clocks before the first headline are not counted, like in org
+        let minutes = document
+            .root
+            .sub_headlines()
+            .map(|headline| headline.clocked_time(span))
+            .sum();
+        FileReport {
+            name,
+            minutes,
+            rows,
+        }
+    }
+}
+
+/// add the sub headlines of `headline` with clocked time to `rows`
+fn collect_rows<'t>(
+    headline: &'t HeadlineGroup<'t>,
+    path: &mut Vec<&'t str>,
+    span: Span,
+    max_level: usize,
+    rows: &mut Vec<ReportRow<'t>>,
+) {
+    for sub_headline in headline.sub_headlines() {
+        let level = sub_headline.headline.level();
+        let minutes = sub_headline.clocked_time(span);
+        if level > max_level || minutes == 0 {
+            continue;
+        }
+
+        path.push(sub_headline.headline.title());
+        rows.push(ReportRow {
+            level,
+            path: path.clone(),
+            minutes,
+        });
+        collect_rows(sub_headline, path, span, max_level, rows);
+        path.pop();
+    }
+}
+
+/// `|` would end a table cell
+fn escape_cell(text: &str) -> Cow<'_, str> {
+    if text.contains('|') {
+        Cow::Owned(text.replace('|', "\\vert{}"))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+impl<'t> Display for ClockReport<'t> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.table())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_org_text;
+
+    const TEST_STATES: [&str; 3] = ["TODO", "STARTED", "DONE"];
+
+    const CLOCK_TEXT: &str = "* project
+:LOGBOOK:
+CLOCK: [2020-02-28 Fri 09:00]--[2020-02-28 Fri 10:30] =>  1:30
+:END:
+** task
+CLOCK: [2020-02-27 Thu 09:00]--[2020-02-27 Thu 11:00] =>  2:00
+*** a | detail
+CLOCK: [2020-02-24 Mon 20:00]--[2020-02-24 Mon 20:30] =>  0:30
+** old task
+CLOCK: [2020-01-02 Thu 09:00]--[2020-01-02 Thu 10:00] =>  1:00
+* notes
+";
+
+    const WEEK_REPORT: &str = "| Headline     | Time   |      |
+|--------------+--------+------|
+| *Total time* | *4:00* |      |
+|--------------+--------+------|
+| project      | 4:00   |      |
+| \\_  task     |        | 2:30 |
+";
+
+    #[test]
+    fn clock_report() {
+        let document = parse_org_text(CLOCK_TEXT, TEST_STATES.to_vec()).unwrap();
+        let today = Date::parse("2020-02-28").unwrap();
+
+        let span = Span::parse_block("thisweek", today).unwrap();
+        assert_eq!(
+            span,
+            Span::new(Date::parse("2020-02-24"), Date::parse("2020-03-01"))
+        );
+        let report = ClockReport::new(&document, span, 2);
+        assert_eq!(report.minutes(), 240);
+        assert_eq!(format!("{}", report), WEEK_REPORT);
+
+        let report = ClockReport::new(&document, Span::all(), 3);
+        let rows: Vec<_> = report.files[0]
+            .rows
+            .iter()
+            .map(|row| (row.path.join("/"), row.minutes))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                ("project".to_string(), 300),
+                ("project/task".to_string(), 150),
+                ("project/task/a | detail".to_string(), 30),
+                ("project/old task".to_string(), 60),
+            ]
+        );
+
+        let today = Span::parse_block("today", today).unwrap();
+        let report =
+            ClockReport::for_files(&[("a.org", &document), ("b.org", &document)], today, 1);
+        assert_eq!(
+            format!("{}", report),
+            "| File  | Headline         | Time   |
+|-------+------------------+--------|
+|       | ALL *Total time* | *3:00* |
+|-------+------------------+--------|
+| a.org | *File time*      | *1:30* |
+|       | project          | 1:30   |
+|-------+------------------+--------|
+| b.org | *File time*      | *1:30* |
+|       | project          | 1:30   |
+"
+        );
+    }
+
+    #[test]
+    fn parse_blocks() {
+        let today = Date::parse("2020-03-11").unwrap();
+        let span = |block| Span::parse_block(block, today).map(|span| (span.from(), span.to()));
+        let dates = |from, to| Some((Date::parse(from), Date::parse(to)));
+
+        assert_eq!(span("yesterday"), dates("2020-03-10", "2020-03-10"));
+        assert_eq!(span("lastweek"), dates("2020-03-02", "2020-03-08"));
+        assert_eq!(span("thismonth"), dates("2020-03-01", "2020-03-31"));
+        assert_eq!(span("lastmonth"), dates("2020-02-01", "2020-02-29"));
+        assert_eq!(span("lastyear"), dates("2019-01-01", "2019-12-31"));
+        assert_eq!(span("2020-02-28"), dates("2020-02-28", "2020-02-28"));
+        assert_eq!(span("someday"), None);
+    }
+}
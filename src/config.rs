@@ -1,13 +1,22 @@
-use crate::{error, status_labels::StatusLabels};
+use crate::{error, priority::Priorities, status_labels::StatusLabels};
 
 pub struct Config {
     pub file_path: String,
     pub status_labels: StatusLabels,
+    pub priorities: Priorities,
+    /// read `a.` and `a)` as list bullets, like `org-list-allow-alphabetical`
+    pub allow_alphabetical_lists: bool,
+    /// tags that sub-headlines do not inherit, like `org-tags-exclude-from-inheritance`
+    pub tags_exclude_from_inheritance: Vec<String>,
 }
 
+#[derive(Default)]
 pub struct Builder {
     file_path: Option<String>,
     status_labels: Option<StatusLabels>,
+    priorities: Option<Priorities>,
+    allow_alphabetical_lists: bool,
+    tags_exclude_from_inheritance: Vec<String>,
 }
 
 impl Builder {
@@ -19,10 +28,14 @@ impl Builder {
         }?;
 
         let status_labels = self.status_labels.unwrap_or_default();
+        let priorities = self.priorities.unwrap_or_default();
 
         Ok(Config {
             file_path,
             status_labels,
+            priorities,
+            allow_alphabetical_lists: self.allow_alphabetical_lists,
+            tags_exclude_from_inheritance: self.tags_exclude_from_inheritance,
         })
     }
 
@@ -35,4 +48,19 @@ impl Builder {
         self.status_labels.replace(labels);
         self
     }
+
+    pub fn priorities(mut self, priorities: Priorities) -> Self {
+        self.priorities.replace(priorities);
+        self
+    }
+
+    pub fn allow_alphabetical_lists(mut self, allow: bool) -> Self {
+        self.allow_alphabetical_lists = allow;
+        self
+    }
+
+    pub fn tags_exclude_from_inheritance(mut self, tags: Vec<String>) -> Self {
+        self.tags_exclude_from_inheritance = tags;
+        self
+    }
 }
@@ -1,12 +1,149 @@
 use std::{fmt, fmt::Display};
 
+use crate::{
+    block::Block,
+    clock::Clock,
+    dynamic_block::DynamicBlock,
+    error::Diagnostic,
+    footnote::FootnoteDefinition,
+    inline::{parse_inline, Inline},
+    keyword::Keyword,
+    statistics::Statistics,
+    table::Table,
+};
+
 #[derive(Debug)]
 pub enum Content<'t> {
-    // TODO Drawer(drawer, content)
+    Block(Block<'t>),
+    Clock(Clock<'t>),
+    Drawer {
+        name: &'t str,
+        contents: Vec<Content<'t>>,
+        /// the `:NAME:` and `:END:` lines as they were written
+        begin: &'t str,
+        end: &'t str,
+    },
+    DynamicBlock(DynamicBlock<'t>),
+    /// `[fn:label] text` at the start of a line
+    Footnote(FootnoteDefinition<'t>),
+    Keyword(Keyword<'t>),
     List(Vec<ListItem<'t>>),
+    Table(Table<'t>),
     Text(Vec<&'t str>),
 }
 
+impl<'t> Content<'t> {
+    /// the kind of a list, determined by its first item,
+    /// or `None` if this is not a list
+    pub fn list_kind(&self) -> Option<ListKind> {
+        match self {
+            Content::List(items) => items.first().map(ListItem::kind),
+            _ => None,
+        }
+    }
+
+    /// the text in this content that can contain inline objects like links.
+    /// this includes the text of list items and their nested lists,
+    /// but not the contents of drawers and footnote definitions, see `children`.
+    pub fn inline_text(&self) -> Vec<&str> {
+        self.inline_text_by_line()
+            .into_iter()
+            .map(|(text, _)| text)
+            .collect()
+    }
+
+    /// the inline text of this content, see `inline_text`.
+    /// table cells come with the line of their row,
+    /// since recalculated cells are not part of the source text.
+    pub fn inline_text_by_line(&self) -> Vec<(&str, Option<&'t str>)> {
+        match self {
+            Content::Footnote(definition) => vec![(definition.content, None)],
+            Content::Keyword(keyword) => vec![(keyword.value, None)],
+            Content::List(items) => items
+                .iter()
+                .flat_map(ListItem::inline_text_by_line)
+                .collect(),
+            Content::Table(table) => table.cells_by_line().collect(),
+            Content::Text(lines) => lines.iter().map(|line| (*line, None)).collect(),
+            Content::Block(_)
+            | Content::Clock(_)
+            | Content::Drawer { .. }
+            | Content::DynamicBlock(_) => Vec::new(),
+        }
+    }
+
+    /// recalculate the tables in this content, including tables
+    /// in drawers and list items. see `Table::recalculate`
+    pub fn recalculate_tables(&mut self, diagnostics: &mut Vec<Diagnostic>) {
+        match self {
+            Content::Table(table) => diagnostics.extend(table.recalculate()),
+            Content::Drawer { contents, .. }
+            | Content::Footnote(FootnoteDefinition { contents, .. }) => {
+                for content in contents {
+                    content.recalculate_tables(diagnostics);
+                }
+            }
+            Content::List(items) => {
+                for content in items.iter_mut().flat_map(|item| item.contents.iter_mut()) {
+                    content.recalculate_tables(diagnostics);
+                }
+            }
+            Content::Block(_)
+            | Content::Clock(_)
+            | Content::DynamicBlock(_)
+            | Content::Keyword(_)
+            | Content::Text(_) => {}
+        }
+    }
+
+    /// the clock lines in this content and its children
+    pub fn clocks(&self) -> Vec<&Clock<'t>> {
+        match self {
+            Content::Clock(clock) => vec![clock],
+            content => content
+                .children()
+                .iter()
+                .flat_map(Content::clocks)
+                .collect(),
+        }
+    }
+
+    /// the dynamic blocks in this content and its children
+    pub fn dynamic_blocks(&self) -> Vec<&DynamicBlock<'t>> {
+        match self {
+            Content::DynamicBlock(block) => vec![block],
+            content => content
+                .children()
+                .iter()
+                .flat_map(Content::dynamic_blocks)
+                .collect(),
+        }
+    }
+
+    /// the dynamic blocks in this content and its children,
+    /// in the same order as `dynamic_blocks`
+    pub fn dynamic_blocks_mut(&mut self) -> Vec<&mut DynamicBlock<'t>> {
+        match self {
+            Content::DynamicBlock(block) => vec![block],
+            Content::Drawer { contents, .. }
+            | Content::Footnote(FootnoteDefinition { contents, .. }) => contents
+                .iter_mut()
+                .flat_map(Content::dynamic_blocks_mut)
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// the content nested inside of this content, e.g. the contents of a drawer
+    pub fn children(&self) -> &[Content<'t>] {
+        match self {
+            Content::Drawer { contents, .. } => contents,
+            Content::Footnote(definition) => &definition.contents,
+            _ => &[],
+        }
+    }
+}
+
 impl<'t> From<Vec<ListItem<'t>>> for Content<'t> {
     fn from(items: Vec<ListItem<'t>>) -> Self {
         Content::List(items)
@@ -16,11 +153,29 @@ impl<'t> From<Vec<ListItem<'t>>> for Content<'t> {
 impl<'t> Display for Content<'t> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            Content::Block(block) => write!(f, "{}", block)?,
+            Content::Clock(clock) => writeln!(f, "{}", clock)?,
+            Content::DynamicBlock(block) => write!(f, "{}", block)?,
+            Content::Drawer {
+                contents,
+                begin,
+                end,
+                ..
+            } => {
+                writeln!(f, "{}", begin)?;
+                for content in contents {
+                    write!(f, "{}", content)?;
+                }
+                writeln!(f, "{}", end)?;
+            }
+            Content::Footnote(definition) => write!(f, "{}", definition)?,
+            Content::Keyword(keyword) => writeln!(f, "{}", keyword)?,
             Content::List(items) => {
                 for item in items {
-                    writeln!(f, "{}", item)?;
+                    write!(f, "{}", item)?;
                 }
             }
+            Content::Table(table) => write!(f, "{}", table)?,
             Content::Text(lines) => {
                 for line in lines {
                     writeln!(f, "{}", line)?;
@@ -33,15 +188,226 @@ impl<'t> Display for Content<'t> {
 
 #[derive(Debug)]
 pub struct ListItem<'t> {
+    /// the indentation of the bullet
+    pub indent: usize,
+    /// the whitespace before the bullet as it was written
+    pub indent_text: &'t str,
     pub bullet: Bullet,
+    /// the number set by a `[@5]` cookie in an ordered list
+    pub counter: Option<usize>,
+    /// the counter as written in the cookie, e.g. `5` or `b`
+    pub counter_text: Option<&'t str>,
+    pub checkbox: Option<Checkbox>,
+    /// the term of a description list item, `- term :: description`
+    pub tag: Option<&'t str>,
     pub content: &'t str,
+    pub statistics: Option<Statistics>,
+    /// the lines after the first line of the item and nested lists
+    pub contents: Vec<Content<'t>>,
+    /// the first line as it was written
+    pub raw: Option<&'t str>,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum ListKind {
+    Unordered,
+    Ordered,
+    /// an unordered list of `term :: description` items
+    Description,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Checkbox {
+    /// `[ ]`
+    Unchecked,
+    /// `[X]`
+    Checked,
+    /// `[-]`
+    Partial,
+}
+
+impl<'t> ListItem<'t> {
+    /// create a list item from a bullet and the text after it,
+    /// which may start with a checkbox
+    pub fn new(indent_text: &'t str, bullet: Bullet, text: &'t str) -> Self {
+        let (counter_text, text) = match parse_counter(text).filter(|_| bullet.index().is_some()) {
+            Some((counter, text)) => (Some(counter), text),
+            None => (None, text),
+        };
+        let (checkbox, text) = match Checkbox::parse(text) {
+            Some((checkbox, text)) => (Some(checkbox), text),
+            None => (None, text),
+        };
+        let (tag, content) = match bullet.index() {
+            None => match split_tag(text) {
+                Some((tag, content)) => (Some(tag), content),
+                None => (None, text),
+            },
+            Some(_) => (None, text),
+        };
+        ListItem {
+            indent: indent_text.chars().count(),
+            indent_text,
+            bullet,
+            counter: counter_text.and_then(counter_value),
+            counter_text,
+            checkbox,
+            tag,
+            content,
+            statistics: Statistics::find(content),
+            contents: Vec::new(),
+            raw: None,
+        }
+    }
+
+    /// parse the first line of a list item.
+    /// `a.` and `a)` are only bullets if `alphabetical_lists` is set.
+    pub fn parse(line: &'t str, alphabetical_lists: bool) -> Option<Self> {
+        let indent = &line[..line.len() - line.trim_start().len()];
+        let text = line.trim();
+        let (bullet, rem) = text.split_at(text.find(' ')?);
+        let bullet = Bullet::parse(bullet)?;
+        if bullet == Bullet::Star && indent.is_empty() {
+            return None;
+        }
+        if !alphabetical_lists && matches!(bullet, Bullet::Alphabetic(..)) {
+            return None;
+        }
+        Some(ListItem {
+            raw: Some(line),
+            ..ListItem::new(indent, bullet, rem.trim())
+        })
+    }
+
+    /// the parts of the first line, to tell if it was changed
+    fn first_line(&self) -> impl PartialEq + '_ {
+        (
+            (self.indent, self.indent_text, self.bullet),
+            (self.counter, self.counter_text, self.checkbox),
+            (self.tag, self.content, &self.statistics),
+        )
+    }
+
+    /// the kind of list this item starts
+    pub fn kind(&self) -> ListKind {
+        match (self.bullet.index(), self.tag) {
+            (Some(_), _) => ListKind::Ordered,
+            (None, Some(_)) => ListKind::Description,
+            (None, None) => ListKind::Unordered,
+        }
+    }
+
+    /// the text of the first line of this item parsed into inline objects
+    pub fn content_objects(&self) -> Vec<Inline<'t>> {
+        parse_inline(self.content)
+    }
+
+    /// the text of this item, its continuation lines and nested lists
+    pub fn inline_text(&self) -> Vec<&str> {
+        self.inline_text_by_line()
+            .into_iter()
+            .map(|(text, _)| text)
+            .collect()
+    }
+
+    /// the inline text of this item with the lines of table cells,
+    /// see `Content::inline_text_by_line`
+    pub fn inline_text_by_line(&self) -> Vec<(&str, Option<&'t str>)> {
+        self.tag
+            .into_iter()
+            .chain(Some(self.content))
+            .map(|text| (text, None))
+            .chain(self.contents.iter().flat_map(Content::inline_text_by_line))
+            .collect()
+    }
+
+    /// the items of lists nested in this item
+    pub fn children(&self) -> impl Iterator<Item = &ListItem<'t>> {
+        self.contents
+            .iter()
+            .filter_map(|content| match content {
+                Content::List(items) => Some(items.iter()),
+                _ => None,
+            })
+            .flatten()
+    }
+
+    /// recompute the statistics cookies in this item and its children
+    /// by counting the checkboxes of the children
+    pub fn update_statistics(&mut self) {
+        for content in self.contents.iter_mut() {
+            if let Content::List(items) = content {
+                for item in items {
+                    item.update_statistics();
+                }
+            }
+        }
+
+        let (done, total) = Checkbox::count(self.children().filter_map(|child| child.checkbox));
+        if let Some(statistics) = self.statistics.as_mut() {
+            statistics.update(done, total);
+        }
+    }
+}
+
+/// split `term :: description` into the term and the description
+fn split_tag(text: &str) -> Option<(&str, &str)> {
+    let (tag, content) = match text.find(" :: ") {
+        Some(i) => (&text[..i], &text[i + 4..]),
+        None => (text.strip_suffix(" ::")?, ""),
+    };
+    let tag = tag.trim();
+    if tag.is_empty() {
+        None
+    } else {
+        Some((tag, content.trim()))
+    }
+}
+
+impl Checkbox {
+    /// count the checked boxes and all boxes, respectively
+    pub fn count<I: Iterator<Item = Checkbox>>(checkboxes: I) -> (usize, usize) {
+        checkboxes.fold((0, 0), |(done, total), checkbox| match checkbox {
+            Checkbox::Checked => (done + 1, total + 1),
+            _ => (done, total + 1),
+        })
+    }
+
+    /// parse a checkbox from the front of `text`
+    /// and return the remaining text
+    pub fn parse(text: &str) -> Option<(Checkbox, &str)> {
+        let checkbox = match text.get(..3)? {
+            "[ ]" => Checkbox::Unchecked,
+            "[X]" | "[x]" => Checkbox::Checked,
+            "[-]" => Checkbox::Partial,
+            _ => return None,
+        };
+        let rem = &text[3..];
+        if rem.is_empty() || rem.starts_with(char::is_whitespace) {
+            Some((checkbox, rem.trim_start()))
+        } else {
+            None
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub enum Bullet {
     Minus,
     Plus,
-    Numeric(usize),
+    /// `*` is only a bullet when it is indented,
+    /// otherwise it is a headline
+    Star,
+    /// `1.` or `1)`
+    Numeric(usize, Delimiter),
+    /// `a.` or `a)`
+    Alphabetic(char, Delimiter),
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Delimiter {
+    Period,
+    Parenthesis,
 }
 
 impl Bullet {
@@ -49,34 +415,131 @@ impl Bullet {
         match s {
             "-" => Some(Bullet::Minus),
             "+" => Some(Bullet::Plus),
-            _ => s.split('.').next().and_then(|num_str| {
-                num_str
-                    .parse::<usize>()
-                    .map(|num| Bullet::Numeric(num))
-                    .ok()
-            }),
+            "*" => Some(Bullet::Star),
+            _ => {
+                let (counter, delimiter) = if let Some(counter) = s.strip_suffix('.') {
+                    (counter, Delimiter::Period)
+                } else {
+                    (s.strip_suffix(')')?, Delimiter::Parenthesis)
+                };
+
+                let mut chars = counter.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(letter), None) if letter.is_ascii_alphabetic() => {
+                        Some(Bullet::Alphabetic(letter, delimiter))
+                    }
+                    _ if counter.chars().all(|ch| ch.is_ascii_digit()) => counter
+                        .parse::<usize>()
+                        .map(|num| Bullet::Numeric(num, delimiter))
+                        .ok(),
+                    _ => None,
+                }
+            }
         }
     }
 
+    /// check if an item with this bullet continues a list started by `other`.
+    /// unordered bullets continue unordered lists and
+    /// ordered bullets continue ordered lists.
     pub fn matches(&self, other: &Bullet) -> bool {
-        if self == other {
-            true
-        } else {
-            self.index().is_some()
-        }
+        self.index().is_some() == other.index().is_some()
     }
 
+    /// the position of an ordered bullet, starting from 1.
+    /// letters count from `a` or `A`.
     pub fn index(&self) -> Option<usize> {
         match self {
-            Bullet::Numeric(index) => Some(*index),
+            Bullet::Numeric(index, _) => Some(*index),
+            Bullet::Alphabetic(letter, _) => Some(alphabetic_index(*letter)),
             _ => None,
         }
     }
 }
 
+fn alphabetic_index(letter: char) -> usize {
+    (letter.to_ascii_lowercase() as usize) - ('a' as usize) + 1
+}
+
+/// parse a `[@5]` or `[@b]` counter-set cookie from the front of `text`
+/// and return the counter as written with the remaining text
+fn parse_counter(text: &str) -> Option<(&str, &str)> {
+    let end = text.find(']')?;
+    let counter = text[..end].strip_prefix("[@")?;
+    let rem = &text[end + 1..];
+    if !rem.is_empty() && !rem.starts_with(char::is_whitespace) {
+        return None;
+    }
+    counter_value(counter)?;
+    Some((counter, rem.trim_start()))
+}
+
+/// the number of a counter like `5` or `b`
+fn counter_value(counter: &str) -> Option<usize> {
+    let mut chars = counter.chars();
+    match (chars.next(), chars.next()) {
+        (Some(letter), None) if letter.is_ascii_alphabetic() => Some(alphabetic_index(letter)),
+        _ if counter.chars().all(|ch| ch.is_ascii_digit()) => counter.parse().ok(),
+        _ => None,
+    }
+}
+
 impl<'t> Display for ListItem<'t> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} {}", self.bullet, self.content)
+        // print the first line as written unless the item was changed
+        let parsed = self.raw.and_then(|raw| ListItem::parse(raw, true));
+        match (self.raw, parsed) {
+            (Some(raw), Some(parsed)) if parsed.first_line() == self.first_line() => {
+                writeln!(f, "{}", raw)?;
+            }
+            _ => self.write_first_line(f)?,
+        }
+
+        for content in &self.contents {
+            write!(f, "{}", content)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'t> ListItem<'t> {
+    fn write_first_line(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.indent_text.chars().count() == self.indent {
+            write!(f, "{}", self.indent_text)?;
+        } else {
+            write!(f, "{}", " ".repeat(self.indent))?;
+        }
+        write!(f, "{}", self.bullet)?;
+        if let Some(counter) = self.counter {
+            // print the counter as written unless it was changed
+            match self.counter_text {
+                Some(text) if counter_value(text) == Some(counter) => write!(f, " [@{}]", text)?,
+                _ => write!(f, " [@{}]", counter)?,
+            }
+        }
+        if let Some(checkbox) = self.checkbox {
+            write!(f, " {}", checkbox)?;
+        }
+        if let Some(tag) = self.tag {
+            write!(f, " {} ::", tag)?;
+        }
+        if !self.content.is_empty() {
+            write!(f, " ")?;
+            match &self.statistics {
+                Some(statistics) => statistics.write_text(f, self.content)?,
+                None => write!(f, "{}", self.content)?,
+            }
+        }
+        writeln!(f)
+    }
+}
+
+impl Display for Checkbox {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Checkbox::Unchecked => write!(f, "[ ]"),
+            Checkbox::Checked => write!(f, "[X]"),
+            Checkbox::Partial => write!(f, "[-]"),
+        }
     }
 }
 
@@ -85,7 +548,18 @@ impl Display for Bullet {
         match self {
             Bullet::Minus => write!(f, "-"),
             Bullet::Plus => write!(f, "+"),
-            Bullet::Numeric(i) => write!(f, "{}.", i),
+            Bullet::Star => write!(f, "*"),
+            Bullet::Numeric(i, delimiter) => write!(f, "{}{}", i, delimiter),
+            Bullet::Alphabetic(letter, delimiter) => write!(f, "{}{}", letter, delimiter),
+        }
+    }
+}
+
+impl Display for Delimiter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Delimiter::Period => write!(f, "."),
+            Delimiter::Parenthesis => write!(f, ")"),
         }
     }
 }
@@ -0,0 +1,183 @@
+use std::{borrow::Cow, fmt, fmt::Display};
+
+use crate::{
+    block::{parse_header_args, strip_prefix_ignore_case, tokenize},
+    error::Result,
+    object::Document,
+};
+
+const BEGIN: &str = "#+BEGIN:";
+const END: &str = "#+END:";
+
+/// a dynamic block whose body is generated, e.g.
+/// ```text
+/// #+BEGIN: clocktable :maxlevel 2 :block thisweek
+/// #+END:
+/// ```
+#[derive(Debug)]
+pub struct DynamicBlock<'t> {
+    pub name: &'t str,
+    /// parameters like `(":maxlevel", "2")`
+    pub parameters: Vec<(&'t str, &'t str)>,
+    /// the lines between the begin and end lines, kept verbatim
+    pub body: Vec<Cow<'t, str>>,
+    /// the `#+BEGIN:` line as written
+    pub begin: &'t str,
+    /// the `#+END:` line as written, if the block was parsed with it
+    pub end: Option<&'t str>,
+}
+
+/// generates the body of dynamic blocks, see `BlockWriters`
+pub trait BlockWriter {
+    /// the new body of `block` in `document`, without the begin and end lines
+    fn write(&self, block: &DynamicBlock<'_>, document: &Document<'_>) -> Result<String>;
+}
+
+impl<F> BlockWriter for F
+where
+    F: Fn(&DynamicBlock<'_>, &Document<'_>) -> Result<String>,
+{
+    fn write(&self, block: &DynamicBlock<'_>, document: &Document<'_>) -> Result<String> {
+        self(block, document)
+    }
+}
+
+/// the block writers of an application by block name,
+/// see `Document::update_dynamic_blocks`
+#[derive(Default)]
+pub struct BlockWriters {
+    writers: Vec<(String, Box<dyn BlockWriter>)>,
+}
+
+impl<'t> DynamicBlock<'t> {
+    /// parse a `#+BEGIN: name :parameter value` line
+    /// into a block with an empty body
+    pub fn parse_begin(line: &'t str) -> Option<DynamicBlock<'t>> {
+        let rem = strip_prefix_ignore_case(line.trim(), BEGIN)?.trim_start();
+        let name_end = rem.find(char::is_whitespace).unwrap_or(rem.len());
+        let (name, parameters) = rem.split_at(name_end);
+        if name.is_empty() {
+            return None;
+        }
+
+        Some(DynamicBlock {
+            name,
+            parameters: parse_header_args(parameters, &tokenize(parameters)),
+            body: Vec::new(),
+            begin: line,
+            end: None,
+        })
+    }
+
+    /// check if `line` is the end of a dynamic block
+    pub fn is_end(line: &str) -> bool {
+        strip_prefix_ignore_case(line.trim(), END).is_some_and(|rem| rem.trim().is_empty())
+    }
+
+    /// get the value of a parameter, e.g. `:maxlevel`
+    pub fn parameter(&self, key: &str) -> Option<&'t str> {
+        self.parameters
+            .iter()
+            .rev()
+            .find(|(parameter, _)| parameter.eq_ignore_ascii_case(key))
+            .map(|(_, value)| *value)
+    }
+
+    /// replace the body with generated text
+    pub fn set_body(&mut self, text: &str) {
+        self.body = text
+            .lines()
+            .map(|line| Cow::Owned(line.to_string()))
+            .collect();
+    }
+}
+
+impl BlockWriters {
+    pub fn new() -> Self {
+        BlockWriters::default()
+    }
+
+    /// use `writer` for blocks named `name`, ignoring case.
+    /// replaces the writer registered for `name` before, if any.
+    pub fn register<W: BlockWriter + 'static>(mut self, name: &str, writer: W) -> Self {
+        self.writers
+            .retain(|(other, _)| !other.eq_ignore_ascii_case(name));
+        self.writers.push((name.to_string(), Box::new(writer)));
+        self
+    }
+
+    /// the writer for blocks named `name`
+    pub fn get(&self, name: &str) -> Option<&dyn BlockWriter> {
+        self.writers
+            .iter()
+            .find(|(other, _)| other.eq_ignore_ascii_case(name))
+            .map(|(_, writer)| writer.as_ref())
+    }
+}
+
+impl<'t> Display for DynamicBlock<'t> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // print the begin line as written unless the name or parameters were changed
+        let parsed = DynamicBlock::parse_begin(self.begin);
+        if parsed.map(|block| (block.name, block.parameters))
+            == Some((self.name, self.parameters.clone()))
+        {
+            writeln!(f, "{}", self.begin)?;
+        } else {
+            write!(f, "{} {}", BEGIN, self.name)?;
+            for (key, value) in &self.parameters {
+                write!(f, " {}", key)?;
+                if !value.is_empty() {
+                    write!(f, " {}", value)?;
+                }
+            }
+            writeln!(f)?;
+        }
+
+        for line in &self.body {
+            writeln!(f, "{}", line)?;
+        }
+        writeln!(f, "{}", self.end.unwrap_or(END))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_dynamic_blocks() {
+        let block = DynamicBlock::parse_begin(
+            "#+BEGIN: clocktable :maxlevel 2 :tstart \"<2020-02-24 Mon>\"",
+        )
+        .unwrap();
+        assert_eq!(block.name, "clocktable");
+        assert_eq!(
+            block.parameters,
+            vec![(":maxlevel", "2"), (":tstart", "\"<2020-02-24 Mon>\"")]
+        );
+        assert_eq!(block.parameter(":MAXLEVEL"), Some("2"));
+        assert_eq!(
+            format!("{}", block),
+            "#+BEGIN: clocktable :maxlevel 2 :tstart \"<2020-02-24 Mon>\"\n#+END:\n"
+        );
+
+        let mut block = DynamicBlock::parse_begin("  #+begin: tasks   :done  t").unwrap();
+        assert_eq!(block.name, "tasks");
+        block.end = Some("  #+end:");
+        assert_eq!(
+            format!("{}", block),
+            "  #+begin: tasks   :done  t\n  #+end:\n"
+        );
+        block.parameters.push((":tags", "work"));
+        assert_eq!(
+            format!("{}", block),
+            "#+BEGIN: tasks :done t :tags work\n  #+end:\n"
+        );
+        assert!(DynamicBlock::parse_begin("#+BEGIN:").is_none());
+        assert!(DynamicBlock::parse_begin("#+BEGIN_SRC rust").is_none());
+
+        assert!(DynamicBlock::is_end("  #+end: "));
+        assert!(!DynamicBlock::is_end("#+END_SRC"));
+    }
+}
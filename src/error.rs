@@ -11,6 +11,20 @@ pub enum OrgError {
     Unexpected(String),
 }
 
+/// a warning about a possible formatting error
+/// that did not prevent the text from being parsed
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn new(line: Option<usize>, message: String) -> Self {
+        Diagnostic { line, message }
+    }
+}
+
 impl OrgError {
     pub fn parse_error(line_num: usize, msg: &'static str) -> OrgError {
         OrgError::ParseError(Some(line_num), msg.to_string())
@@ -40,6 +54,15 @@ impl fmt::Display for OrgError {
     }
 }
 
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "warning: line {}: {}", line, self.message),
+            None => write!(f, "warning: {}", self.message),
+        }
+    }
+}
+
 impl error::Error for OrgError {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match self {
@@ -0,0 +1,188 @@
+use std::{collections::HashMap, fmt, fmt::Display};
+
+use crate::{
+    content::Content,
+    inline::{parse_inline, Inline},
+};
+
+/// a footnote definition at the start of a line, e.g.
+/// ```text
+/// [fn:1] the text of the footnote
+/// ```
+#[derive(Debug)]
+pub struct FootnoteDefinition<'t> {
+    pub label: &'t str,
+    /// the text after the label on the first line
+    pub content: &'t str,
+    /// the content after the first line, up to the next definition,
+    /// the next headline or two blank lines
+    pub contents: Vec<Content<'t>>,
+}
+
+/// the definition of a footnote, see `Document::resolve_footnote`
+#[derive(Debug, Clone)]
+pub enum Footnote<'t> {
+    /// `[fn:label] text` at the start of a line
+    Definition(&'t FootnoteDefinition<'t>),
+    /// the definition of an inline footnote, `[fn:label:text]` or `[fn::text]`
+    Inline(Vec<Inline<'t>>),
+}
+
+impl<'t> FootnoteDefinition<'t> {
+    /// parse the first line of a definition, `[fn:label] text`.
+    /// definitions can't be indented.
+    pub fn parse(line: &'t str) -> Option<Self> {
+        let rem = line.strip_prefix("[fn:")?;
+        let end = rem.find(']')?;
+        let label = &rem[..end];
+        if label.is_empty() || !label.chars().all(is_label_char) {
+            return None;
+        }
+        Some(FootnoteDefinition {
+            label,
+            content: rem[end + 1..].trim(),
+            contents: Vec::new(),
+        })
+    }
+
+    /// the text of the first line parsed into inline objects
+    pub fn content_objects(&self) -> Vec<Inline<'t>> {
+        parse_inline(self.content)
+    }
+}
+
+/// the footnote definitions of a document by label,
+/// to resolve many references without searching the document for each.
+/// see `Document::footnotes`
+#[derive(Debug, Default)]
+pub struct Footnotes<'t> {
+    definitions: HashMap<&'t str, Footnote<'t>>,
+}
+
+impl<'t> Footnotes<'t> {
+    /// collect the `definitions` and the labeled inline footnotes in `references`.
+    /// definitions at the start of a line come before inline footnotes,
+    /// and the first definition of a label is used.
+    pub fn new<I>(definitions: I, references: &[Inline<'t>]) -> Self
+    where
+        I: IntoIterator<Item = &'t FootnoteDefinition<'t>>,
+    {
+        let mut footnotes = HashMap::new();
+        for definition in definitions {
+            footnotes
+                .entry(definition.label)
+                .or_insert(Footnote::Definition(definition));
+        }
+        for reference in references {
+            if let Inline::FootnoteReference {
+                label: Some(label),
+                definition: Some(definition),
+            } = reference
+            {
+                footnotes
+                    .entry(*label)
+                    .or_insert_with(|| Footnote::Inline(definition.clone()));
+            }
+        }
+        Footnotes {
+            definitions: footnotes,
+        }
+    }
+
+    /// the definition of the footnote labeled `label`
+    pub fn get(&self, label: &str) -> Option<&Footnote<'t>> {
+        self.definitions.get(label)
+    }
+
+    /// find the definition of the footnote `reference`.
+    /// inline footnotes are their own definition.
+    pub fn resolve(&self, reference: &Inline<'t>) -> Option<Footnote<'t>> {
+        match reference {
+            Inline::FootnoteReference {
+                definition: Some(definition),
+                ..
+            } => Some(Footnote::Inline(definition.clone())),
+            Inline::FootnoteReference {
+                label: Some(label), ..
+            } => self.get(label).cloned(),
+            _ => None,
+        }
+    }
+}
+
+/// check if `ch` can be part of a footnote label
+pub fn is_label_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '-' || ch == '_'
+}
+
+/// the footnote references in `objects`, including references
+/// nested in markup and in the definitions of inline footnotes
+pub fn footnote_references<'a, 't>(objects: &'a [Inline<'t>]) -> Vec<&'a Inline<'t>> {
+    let mut references = Vec::new();
+    for object in objects {
+        match object {
+            Inline::FootnoteReference { definition, .. } => {
+                references.push(object);
+                if let Some(definition) = definition {
+                    references.extend(footnote_references(definition));
+                }
+            }
+            Inline::Bold(objects)
+            | Inline::Italic(objects)
+            | Inline::Underline(objects)
+            | Inline::StrikeThrough(objects)
+            | Inline::Subscript { objects, .. }
+            | Inline::Superscript { objects, .. } => {
+                references.extend(footnote_references(objects))
+            }
+            _ => {}
+        }
+    }
+    references
+}
+
+impl<'t> Display for FootnoteDefinition<'t> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.content.is_empty() {
+            writeln!(f, "[fn:{}]", self.label)?;
+        } else {
+            writeln!(f, "[fn:{}] {}", self.label, self.content)?;
+        }
+        for content in &self.contents {
+            write!(f, "{}", content)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_definitions() {
+        let definition = FootnoteDefinition::parse("[fn:1] the *first* note").unwrap();
+        assert_eq!(definition.label, "1");
+        assert_eq!(definition.content, "the *first* note");
+        assert_eq!(definition.content_objects().len(), 3);
+        assert_eq!(format!("{}", definition), "[fn:1] the *first* note\n");
+
+        assert_eq!(FootnoteDefinition::parse("[fn:note]").unwrap().content, "");
+        assert!(FootnoteDefinition::parse(" [fn:1] indented").is_none());
+        assert!(FootnoteDefinition::parse("[fn::anonymous]").is_none());
+        assert!(FootnoteDefinition::parse("[fn:a b] text").is_none());
+    }
+
+    #[test]
+    fn find_references() {
+        let objects = parse_inline("a[fn:1] *b[fn:2]* [fn::c[fn:3]] [fn:4:d]");
+        let labels: Vec<_> = footnote_references(&objects)
+            .into_iter()
+            .map(|reference| match reference {
+                Inline::FootnoteReference { label, .. } => *label,
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(labels, [Some("1"), Some("2"), None, Some("3"), Some("4")]);
+    }
+}
@@ -1,12 +1,31 @@
-use std::{fmt, fmt::Display};
-
-use crate::{content::Content, iter::*, object::Object};
+use std::{borrow::Cow, fmt, fmt::Display, ops::RangeBounds};
+
+use crate::{
+    clock::Clock,
+    content::{Checkbox, Content},
+    dynamic_block::DynamicBlock,
+    error::Diagnostic,
+    inline::{parse_inline, Inline},
+    iter::*,
+    object::Object,
+    planning::Planning,
+    priority::is_priority,
+    properties::Properties,
+    statistics::Statistics,
+    status_labels::StatusLabels,
+    tags::TagGroups,
+    timestamp::Date,
+};
 
 #[derive(Debug)]
 pub struct HeadlineGroup<'t> {
     pub headline: Headline<'t>,
+    pub planning: Option<Planning>,
+    pub properties: Option<Properties<'t>>,
     pub content: Option<Vec<Content<'t>>>,
     pub sub_headlines: Option<Vec<HeadlineGroup<'t>>>,
+    /// the tags inherited from ancestors and `#+FILETAGS`
+    pub inherited_tags: Vec<&'t str>,
 }
 
 impl<'t> HeadlineGroup<'t> {
@@ -14,6 +33,21 @@ impl<'t> HeadlineGroup<'t> {
         self.content.iter().flat_map(|content| content.iter())
     }
 
+    pub fn planning(&self) -> Option<&Planning> {
+        self.planning.as_ref()
+    }
+
+    pub fn properties(&self) -> Option<&Properties<'t>> {
+        self.properties.as_ref()
+    }
+
+    /// get a property from this headline's property drawer
+    pub fn property(&self, key: &str) -> Option<Cow<'t, str>> {
+        self.properties
+            .as_ref()
+            .and_then(|properties| properties.get(key))
+    }
+
     pub fn content_len(&self) -> usize {
         self.content
             .as_ref()
@@ -21,7 +55,7 @@ impl<'t> HeadlineGroup<'t> {
             .unwrap_or(0)
     }
 
-    pub fn sub_headlines(&'t self) -> SubHeadlines<'_> {
+    pub fn sub_headlines(&'t self) -> SubHeadlines<'t> {
         (&self.sub_headlines).into()
     }
 
@@ -32,7 +66,7 @@ impl<'t> HeadlineGroup<'t> {
             .unwrap_or(0)
     }
 
-    pub fn sub_objects(&'t self) -> SubObjects<'_> {
+    pub fn sub_objects(&'t self) -> SubObjects<'t> {
         self.into_iter()
     }
 
@@ -40,6 +74,146 @@ impl<'t> HeadlineGroup<'t> {
         self.sub_headlines().into()
     }
 
+    /// the inherited tags followed by the headline's own tags, without duplicates
+    pub fn effective_tags(&self) -> Vec<&'t str> {
+        let mut tags: Vec<&'t str> = Vec::new();
+        for &tag in self.inherited_tags.iter().chain(self.headline.tags()) {
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        tags
+    }
+
+    /// check if the effective tags include `tag`
+    /// or a member of `tag` if it is a group tag
+    pub fn has_tag(&self, tag: &str, tag_groups: &TagGroups) -> bool {
+        let tags = tag_groups.expand(tag);
+        self.effective_tags()
+            .iter()
+            .any(|effective| tags.contains(effective))
+    }
+
+    /// set the inherited tags of this headline to `inherited`
+    /// and pass its effective tags on to its sub-headlines.
+    /// tags in `exclude` are not inherited.
+    pub fn inherit_tags(&mut self, inherited: &[&'t str], exclude: &[&str]) {
+        self.inherited_tags = inherited
+            .iter()
+            .filter(|tag| !exclude.contains(tag))
+            .copied()
+            .collect();
+
+        let tags = self.effective_tags();
+        if let Some(sub_headlines) = self.sub_headlines.as_mut() {
+            for sub_headline in sub_headlines {
+                sub_headline.inherit_tags(&tags, exclude);
+            }
+        }
+    }
+
+    /// the clock lines in the content of this headline,
+    /// including the `:LOGBOOK:` drawer, but not in its sub headlines
+    pub fn clocks(&'t self) -> impl Iterator<Item = &'t Clock<'t>> {
+        self.content().flat_map(Content::clocks)
+    }
+
+    /// the minutes clocked on this headline and its sub headlines
+    /// between the start of the first day and the end of the last day of `range`.
+    /// clocks that are still running are not counted.
+    pub fn clocked_time<R: RangeBounds<Date> + Clone>(&'t self, range: R) -> i64 {
+        let own: i64 = self
+            .clocks()
+            .map(|clock| clock.minutes_in(range.clone()))
+            .sum();
+        own + self
+            .sub_headlines()
+            .map(|sub_headline| sub_headline.clocked_time(range.clone()))
+            .sum::<i64>()
+    }
+
+    /// the dynamic blocks of this headline and its sub headlines
+    pub fn dynamic_blocks(&self) -> Vec<&DynamicBlock<'t>> {
+        let sub_headlines = self.sub_headlines.iter().flatten();
+        self.content
+            .iter()
+            .flatten()
+            .flat_map(Content::dynamic_blocks)
+            .chain(sub_headlines.flat_map(HeadlineGroup::dynamic_blocks))
+            .collect()
+    }
+
+    /// the dynamic blocks of this headline and its sub headlines,
+    /// in the same order as `dynamic_blocks`
+    pub fn dynamic_blocks_mut(&mut self) -> Vec<&mut DynamicBlock<'t>> {
+        let sub_headlines = self.sub_headlines.iter_mut().flatten();
+        self.content
+            .iter_mut()
+            .flatten()
+            .flat_map(Content::dynamic_blocks_mut)
+            .chain(sub_headlines.flat_map(HeadlineGroup::dynamic_blocks_mut))
+            .collect()
+    }
+
+    /// recalculate the tables of this headline and its sub headlines
+    pub fn recalculate_tables(&mut self, diagnostics: &mut Vec<Diagnostic>) {
+        for content in self.content.iter_mut().flatten() {
+            content.recalculate_tables(diagnostics);
+        }
+        for sub_headline in self.sub_headlines.iter_mut().flatten() {
+            sub_headline.recalculate_tables(diagnostics);
+        }
+    }
+
+    /// recompute the statistics cookies in this headline and its sub-headlines.
+    /// checkboxes in the content are counted if there are any,
+    /// otherwise sub-headlines with a status are counted
+    /// and those with a done status in `status_labels` are done.
+    pub fn update_statistics(&mut self, status_labels: &StatusLabels) {
+        if let Some(sub_headlines) = self.sub_headlines.as_mut() {
+            for sub_headline in sub_headlines {
+                sub_headline.update_statistics(status_labels);
+            }
+        }
+
+        for content in self.content.iter_mut().flatten() {
+            if let Content::List(items) = content {
+                for item in items {
+                    item.update_statistics();
+                }
+            }
+        }
+
+        if self.headline.statistics.is_none() {
+            return;
+        }
+
+        let checkboxes = self
+            .content()
+            .filter_map(|content| match content {
+                Content::List(items) => Some(items.iter().filter_map(|item| item.checkbox)),
+                _ => None,
+            })
+            .flatten();
+        let (mut done, mut total) = Checkbox::count(checkboxes);
+
+        if total == 0 {
+            let statuses = self
+                .sub_headlines()
+                .filter_map(|sub_headline| sub_headline.headline.status());
+            for status in statuses {
+                total += 1;
+                if status_labels.is_done(status) {
+                    done += 1;
+                }
+            }
+        }
+
+        if let Some(statistics) = self.headline.statistics.as_mut() {
+            statistics.update(done, total);
+        }
+    }
+
     pub fn all_objects(&'t self) -> impl Iterator<Item = Object<'t>> {
         AllObjects::new(self)
     }
@@ -50,7 +224,9 @@ pub struct Headline<'t> {
     level: usize,
     title: &'t str,
     status: Option<&'t str>,
+    priority: Option<char>,
     tags: Option<Vec<&'t str>>,
+    statistics: Option<Statistics>,
 }
 
 impl<'t> Headline<'t> {
@@ -59,7 +235,9 @@ impl<'t> Headline<'t> {
             level: 0,
             title: "root",
             status: None,
+            priority: None,
             tags: None,
+            statistics: None,
         }
     }
 
@@ -73,6 +251,35 @@ impl<'t> Headline<'t> {
     pub fn title(&self) -> &'t str {
         self.title
     }
+
+    /// the title parsed into inline objects like emphasis and links
+    pub fn title_objects(&self) -> Vec<Inline<'t>> {
+        parse_inline(self.title)
+    }
+
+    pub fn status(&self) -> Option<&'t str> {
+        self.status
+    }
+
+    /// check if the status is a done state in `status_labels`
+    pub fn is_done(&self, status_labels: &StatusLabels) -> bool {
+        self.status
+            .map(|status| status_labels.is_done(status))
+            .unwrap_or(false)
+    }
+
+    /// the priority in a `[#A]` cookie after the status
+    pub fn priority(&self) -> Option<char> {
+        self.priority
+    }
+
+    pub fn tags(&self) -> &[&'t str] {
+        self.tags.as_deref().unwrap_or(&[])
+    }
+
+    pub fn statistics(&self) -> Option<&Statistics> {
+        self.statistics.as_ref()
+    }
 }
 
 fn parse_headline<'t>(line: &'t str, possible_states: &[&str]) -> Option<Headline<'t>> {
@@ -84,6 +291,10 @@ fn parse_headline<'t>(line: &'t str, possible_states: &[&str]) -> Option<Headlin
 
     // trim header markers, '*'
     let (_, text) = line.split_at(level);
+    // `*bold*` at the start of a line is not a headline
+    if text.starts_with(|ch: char| !ch.is_whitespace()) {
+        return None;
+    }
     let text = text.trim();
 
     let (status, text) = match parse_status(text, possible_states) {
@@ -91,6 +302,11 @@ fn parse_headline<'t>(line: &'t str, possible_states: &[&str]) -> Option<Headlin
         None => (None, text),
     };
 
+    let (priority, text) = match parse_priority(text) {
+        Some((priority, rem)) => (Some(priority), rem),
+        None => (None, text),
+    };
+
     let (tags, text) = match parse_tags(text) {
         Some((tags, rem)) => (Some(tags), rem),
         None => (None, text),
@@ -102,10 +318,12 @@ fn parse_headline<'t>(line: &'t str, possible_states: &[&str]) -> Option<Headlin
         level,
         title,
         status,
+        priority,
         tags,
+        statistics: Statistics::find(title),
     };
 
-    return Some(headline);
+    Some(headline)
 }
 
 /// parse status from the front of `text`
@@ -113,66 +331,103 @@ fn parse_headline<'t>(line: &'t str, possible_states: &[&str]) -> Option<Headlin
 /// returns the status and the remaining text, respectively,
 /// or None if no status is found.
 pub fn parse_status<'t>(text: &'t str, possible_states: &[&str]) -> Option<(&'t str, &'t str)> {
+    let word = text.split_whitespace().next()?;
     possible_states
         .iter()
-        .find(|&&state| text.starts_with(state))
+        .find(|&&state| word == state)
         .map(|state| text.split_at(state.len()))
         .map(|(status, text)| (status, text.trim()))
 }
 
+/// parse a priority cookie, e.g. `[#A]`, from the front of `text`.
+/// returns the priority and the remaining text, respectively,
+/// or None if there is no priority.
+/// only single character priorities are supported, see `Priorities`.
+fn parse_priority(text: &str) -> Option<(char, &str)> {
+    let rem = text.strip_prefix("[#")?;
+    let mut chars = rem.chars();
+    let priority = chars.next().filter(|ch| is_priority(*ch))?;
+    let rem = chars.as_str().strip_prefix(']')?;
+    if rem.starts_with(|ch: char| !ch.is_whitespace()) {
+        return None;
+    }
+    Some((priority, rem.trim()))
+}
+
 /// parse tags from the end of `text` and
 /// return the trimmed `text` and the tags
-/// or `None` if there are no tags
+/// or `None` if there are no tags.
+/// tags are written `:tag1:tag2:` and contain letters, numbers, `_`, `@`, `#` or `%`.
 fn parse_tags<'t>(text: &'t str) -> Option<(Vec<&'t str>, &'t str)> {
-    text.trim()
-        .rfind(char::is_whitespace)
-        .map(|i| text.split_at(i))
-        .and_then(|(text, tag_str)| {
-            let tags: Vec<&'t str> = tag_str
-                .split_terminator(':')
-                .filter(|tag| !tag.contains(' '))
-                .filter(|tag| tag.len() != 0)
-                .collect();
-
-            if tags.len() == 0 {
-                None
-            } else {
-                Some((tags, text.trim()))
-            }
-        })
+    let text = text.trim();
+    let start = text.rfind(char::is_whitespace).map(|i| i + 1).unwrap_or(0);
+    let (text, tag_str) = text.split_at(start);
+    let tags: Vec<&'t str> = tag_str
+        .strip_prefix(':')?
+        .strip_suffix(':')?
+        .split(':')
+        .collect();
+
+    let is_tag = |tag: &&str| {
+        !tag.is_empty()
+            && tag
+                .chars()
+                .all(|ch| ch.is_alphanumeric() || matches!(ch, '_' | '@' | '#' | '%'))
+    };
+    if tags.iter().all(is_tag) {
+        Some((tags, text.trim()))
+    } else {
+        None
+    }
 }
 
 impl<'t> Display for Headline<'t> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let level_indicator = "*".repeat(self.level);
-        let tag_string = self.tags.as_ref().map(|tags| tags.join(":"));
+        write!(f, "{}", "*".repeat(self.level))?;
+
+        if let Some(status) = self.status {
+            write!(f, " {}", status)?;
+        }
 
-        match (self.status, tag_string) {
-            (Some(status), None) => write!(f, "{} {} {}", level_indicator, status, self.title),
-            (None, Some(tag_string)) => {
-                write!(f, "{} {} {}", level_indicator, self.title, tag_string,)
+        if let Some(priority) = self.priority {
+            write!(f, " [#{}]", priority)?;
+        }
+
+        if !self.title.is_empty() {
+            write!(f, " ")?;
+            match &self.statistics {
+                Some(statistics) => statistics.write_text(f, self.title)?,
+                None => write!(f, "{}", self.title)?,
             }
-            (Some(status), Some(tag_string)) => write!(
-                f,
-                "{} {} {} :{}:",
-                level_indicator, status, self.title, tag_string,
-            ),
-            (None, None) => write!(f, "{} {}", level_indicator, self.title),
         }
+
+        if let Some(tags) = &self.tags {
+            write!(f, " :{}:", tags.join(":"))?;
+        }
+
+        Ok(())
     }
 }
 
 impl<'t> Display for HeadlineGroup<'t> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        writeln!(f, "{}", self.headline)?;
+        // the root of a document has no headline
+        if self.headline.level > 0 {
+            writeln!(f, "{}", self.headline)?;
+        }
 
-        match &self.content {
-            Some(content) => {
-                for object in content {
-                    write!(f, "{}", object)?;
-                }
+        if let Some(planning) = &self.planning {
+            writeln!(f, "{}", planning)?;
+        }
+
+        if let Some(properties) = &self.properties {
+            write!(f, "{}", properties)?;
+        }
+
+        if let Some(content) = &self.content {
+            for object in content {
+                write!(f, "{}", object)?;
             }
-            _ => {}
         }
 
         for headline in self.sub_headlines() {
@@ -184,6 +439,7 @@ impl<'t> Display for HeadlineGroup<'t> {
 }
 
 #[cfg(test)]
+#[allow(non_upper_case_globals)]
 mod tests {
     use super::*;
 
@@ -217,6 +473,179 @@ mod tests {
         .is_some()))
     }
 
+    #[test]
+    fn parse_priorities() {
+        let headline = Headline::parse("* TODO [#A] ship it :work:", &test_states).unwrap();
+        assert_eq!(headline.status(), Some("TODO"));
+        assert_eq!(headline.priority(), Some('A'));
+        assert_eq!(headline.title(), "ship it");
+        assert_eq!(format!("{}", headline), "* TODO [#A] ship it :work:");
+
+        let headline = Headline::parse("** [#3] numeric", &test_states).unwrap();
+        assert_eq!(headline.priority(), Some('3'));
+        assert_eq!(format!("{}", headline), "** [#3] numeric");
+
+        let headline = Headline::parse("* TODO [#10] ten", &test_states).unwrap();
+        assert_eq!(headline.priority(), None);
+        assert_eq!(headline.title(), "[#10] ten");
+        assert_eq!(format!("{}", headline), "* TODO [#10] ten");
+
+        let headline = Headline::parse("* [#a] lowercase", &test_states).unwrap();
+        assert_eq!(headline.priority(), None);
+        assert_eq!(headline.title(), "[#a] lowercase");
+
+        let headline = Headline::parse("* [#A]title", &test_states).unwrap();
+        assert_eq!(headline.priority(), None);
+        assert_eq!(headline.title(), "[#A]title");
+
+        let headline = Headline::parse("* title [#A]", &test_states).unwrap();
+        assert_eq!(headline.priority(), None);
+    }
+
+    #[test]
+    fn parse_title_objects() {
+        let headline = Headline::parse(
+            "* TODO read *the* [[https://orgmode.org][manual]]",
+            &test_states,
+        )
+        .unwrap();
+        let objects = headline.title_objects();
+        assert_eq!(objects[0], Inline::Text("read "));
+        assert_eq!(objects[1], Inline::Bold(vec![Inline::Text("the")]));
+        assert!(matches!(objects[3], Inline::Link(_)));
+
+        assert!(Headline::parse("*bold* text", &test_states).is_none());
+        assert!(Headline::parse("**", &test_states).is_some());
+
+        let headline = Headline::parse("** TODO", &test_states).unwrap();
+        assert_eq!(format!("{}", headline), "** TODO");
+        let headline = Headline::parse("* :tag:", &test_states).unwrap();
+        assert_eq!(format!("{}", headline), "* :tag:");
+    }
+
+    const TAGS_TEXT: &str = "#+FILETAGS: :notes:
+#+TAGS: [ Project : ProjA ProjB ]
+* planning :ProjA:crypt:
+** meeting :work:
+*** agenda
+* other
+";
+
+    #[test]
+    fn effective_tags() {
+        let document = crate::parser::parse_org_text(TAGS_TEXT, test_states.to_vec()).unwrap();
+        let tags = |document: &crate::object::Document| -> Vec<Vec<String>> {
+            document
+                .headlines()
+                .map(|headline| {
+                    headline
+                        .effective_tags()
+                        .iter()
+                        .map(|tag| tag.to_string())
+                        .collect()
+                })
+                .collect()
+        };
+
+        assert_eq!(
+            tags(&document),
+            vec![
+                vec!["notes", "ProjA", "crypt"],
+                vec!["notes", "ProjA", "crypt", "work"],
+                vec!["notes", "ProjA", "crypt", "work"],
+                vec!["notes"],
+            ]
+        );
+
+        let agenda = document.headlines().nth(2).unwrap();
+        assert!(agenda.has_tag("Project", &document.tag_groups));
+        assert!(!agenda.has_tag("ProjB", &document.tag_groups));
+
+        // excluded tags still apply to the headline they are on
+        let config = crate::config::Builder::default()
+            .file_path("tags.org".to_string())
+            .tags_exclude_from_inheritance(vec!["crypt".to_string(), "notes".to_string()])
+            .build()
+            .unwrap();
+        let document = crate::parser::parse_org_text_with_config(TAGS_TEXT, &config).unwrap();
+        assert_eq!(
+            tags(&document),
+            vec![
+                vec!["ProjA", "crypt"],
+                vec!["ProjA", "work"],
+                vec!["ProjA", "work"],
+                Vec::<&str>::new(),
+            ]
+        );
+    }
+
+    const STATISTICS_TEXT: &str = "* project [0/0]
+** DONE first task
+** TODO second task [%]
+- [X] step one
+- [ ] step two
+- [-] step three
+- a plain item
+** STARTED third task
+** a note
+";
+
+    const UPDATED_STATISTICS_TEXT: &str = "* project [1/3]
+** DONE first task
+** TODO second task [33%]
+- [X] step one
+- [ ] step two
+- [-] step three
+- a plain item
+** STARTED third task
+** a note
+";
+
+    #[test]
+    fn update_statistics() {
+        let mut document =
+            crate::parser::parse_org_text(STATISTICS_TEXT, test_states.to_vec()).unwrap();
+        assert_eq!(format!("{}", document), STATISTICS_TEXT);
+
+        document.update_statistics();
+        assert_eq!(format!("{}", document), UPDATED_STATISTICS_TEXT);
+    }
+
+    const CLOCK_TEXT: &str = "* project
+:LOGBOOK:
+CLOCK: [2020-02-28 Fri 09:00]--[2020-02-28 Fri 10:30] =>  1:30
+:END:
+** task
+  :LOGBOOK:
+  CLOCK: [2020-02-29 Sat 23:00]--[2020-03-01 Sun 00:15] =>  1:23
+  CLOCK: [2020-03-02 Mon 09:00]
+  :END:
+CLOCK: [2020-03-02 Mon 08:00]--[2020-03-02 Mon 08:45] =>  0:45
+";
+
+    #[test]
+    fn clocked_time() {
+        let document = crate::parser::parse_org_text(CLOCK_TEXT, test_states.to_vec()).unwrap();
+        assert_eq!(format!("{}", document), CLOCK_TEXT);
+        assert_eq!(document.diagnostics.len(), 1);
+        assert_eq!(document.diagnostics[0].line, Some(6));
+        assert_eq!(
+            document.diagnostics[0].message,
+            "clock duration `1:23` does not match its timestamps, `1:15`"
+        );
+
+        let project = document.root.sub_headlines().next().unwrap();
+        let task = project.sub_headlines().next().unwrap();
+        assert_eq!(project.clocks().count(), 1);
+        assert_eq!(task.clocks().count(), 3);
+
+        let day = |s| Date::parse(s).unwrap();
+        assert_eq!(task.clocked_time(..), 75 + 45);
+        assert_eq!(project.clocked_time(..), 90 + 75 + 45);
+        assert_eq!(project.clocked_time(day("2020-03-01")..), 15 + 45);
+        assert_eq!(project.clocked_time(..=day("2020-02-29")), 90 + 60);
+    }
+
     #[test]
     fn headline_iterator() {
         let content = crate::parser::parse_org_text(count_up_headlines, test_states.to_vec())
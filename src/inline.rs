@@ -0,0 +1,596 @@
+//! parse text into inline objects like emphasis, links and timestamps
+
+use std::{fmt, fmt::Display};
+
+use crate::{footnote::is_label_char, link::Link, timestamp::Timestamp};
+
+/// characters allowed before an opening emphasis marker
+const PRE: &str = "-({'\"";
+/// characters allowed after a closing emphasis marker
+const POST: &str = "-.,;:!?')}[\"\\";
+
+/// entity names and the characters they stand for, e.g. `\alpha` or `\rarr{}`
+const ENTITIES: [(&str, &str); 64] = [
+    ("Alpha", "Α"),
+    ("Beta", "Β"),
+    ("Delta", "Δ"),
+    ("Gamma", "Γ"),
+    ("Lambda", "Λ"),
+    ("Omega", "Ω"),
+    ("Phi", "Φ"),
+    ("Pi", "Π"),
+    ("Psi", "Ψ"),
+    ("Sigma", "Σ"),
+    ("Theta", "Θ"),
+    ("alpha", "α"),
+    ("amp", "&"),
+    ("beta", "β"),
+    ("bull", "•"),
+    ("cent", "¢"),
+    ("check", "✓"),
+    ("chi", "χ"),
+    ("copy", "©"),
+    ("dagger", "†"),
+    ("darr", "↓"),
+    ("deg", "°"),
+    ("delta", "δ"),
+    ("div", "÷"),
+    ("epsilon", "ε"),
+    ("eta", "η"),
+    ("euro", "€"),
+    ("gamma", "γ"),
+    ("ge", "≥"),
+    ("gt", ">"),
+    ("hellip", "…"),
+    ("infin", "∞"),
+    ("iota", "ι"),
+    ("kappa", "κ"),
+    ("lambda", "λ"),
+    ("larr", "←"),
+    ("laquo", "«"),
+    ("ldquo", "“"),
+    ("le", "≤"),
+    ("lt", "<"),
+    ("mdash", "—"),
+    ("mu", "μ"),
+    ("nbsp", "\u{a0}"),
+    ("ndash", "–"),
+    ("ne", "≠"),
+    ("nu", "ν"),
+    ("omega", "ω"),
+    ("phi", "φ"),
+    ("pi", "π"),
+    ("pm", "±"),
+    ("pound", "£"),
+    ("psi", "ψ"),
+    ("raquo", "»"),
+    ("rarr", "→"),
+    ("rdquo", "”"),
+    ("reg", "®"),
+    ("rho", "ρ"),
+    ("sigma", "σ"),
+    ("tau", "τ"),
+    ("theta", "θ"),
+    ("times", "×"),
+    ("to", "→"),
+    ("trade", "™"),
+    ("uarr", "↑"),
+];
+
+/// an object in a line of text
+#[derive(Debug, Clone, PartialEq)]
+pub enum Inline<'t> {
+    Text(&'t str),
+    /// `*bold*`
+    Bold(Vec<Inline<'t>>),
+    /// `/italic/`
+    Italic(Vec<Inline<'t>>),
+    /// `_underline_`
+    Underline(Vec<Inline<'t>>),
+    /// `+strike-through+`
+    StrikeThrough(Vec<Inline<'t>>),
+    /// `=verbatim=`
+    Verbatim(&'t str),
+    /// `~code~`
+    Code(&'t str),
+    Link(Link<'t>),
+    Timestamp(Timestamp),
+    /// `\alpha` or `\alpha{}`
+    Entity {
+        name: &'t str,
+        utf8: &'static str,
+        braces: bool,
+    },
+    /// `\(x\)`, `\[x\]`, `$x$`, `$$x$$` or a LaTeX command like `\frac{1}{2}`
+    LatexFragment(&'t str),
+    /// `[fn:label]`, `[fn:label:definition]` or `[fn::definition]`
+    FootnoteReference {
+        label: Option<&'t str>,
+        definition: Option<Vec<Inline<'t>>>,
+    },
+    /// `a_b` or `a_{b}`
+    Subscript {
+        objects: Vec<Inline<'t>>,
+        braces: bool,
+    },
+    /// `a^b` or `a^{b}`
+    Superscript {
+        objects: Vec<Inline<'t>>,
+        braces: bool,
+    },
+    /// `\\` at the end of a line
+    LineBreak,
+}
+
+/// parse `text` into inline objects
+pub fn parse_inline(text: &str) -> Vec<Inline<'_>> {
+    let mut objects = Vec::new();
+    let mut text_start = 0;
+    let mut i = 0;
+    while let Some(ch) = text[i..].chars().next() {
+        match parse_object(text, i, ch) {
+            Some((object, len)) => {
+                if text_start < i {
+                    objects.push(Inline::Text(&text[text_start..i]));
+                }
+                objects.push(object);
+                i += len;
+                text_start = i;
+            }
+            None => i += ch.len_utf8(),
+        }
+    }
+    if text_start < text.len() {
+        objects.push(Inline::Text(&text[text_start..]));
+    }
+    objects
+}
+
+/// the text of `objects` without markup, e.g. for searching or exporting to plain text
+pub fn plain_text(objects: &[Inline]) -> String {
+    let mut text = String::new();
+    for object in objects {
+        match object {
+            Inline::Text(s) | Inline::Verbatim(s) | Inline::Code(s) | Inline::LatexFragment(s) => {
+                text.push_str(s)
+            }
+            Inline::Bold(objects)
+            | Inline::Italic(objects)
+            | Inline::Underline(objects)
+            | Inline::StrikeThrough(objects)
+            | Inline::Subscript { objects, .. }
+            | Inline::Superscript { objects, .. } => text.push_str(&plain_text(objects)),
+            Inline::Link(link) => match link.description {
+                Some(description) => text.push_str(&plain_text(&parse_inline(description))),
+                None => text.push_str(&link.target()),
+            },
+            Inline::Timestamp(timestamp) => text.push_str(&timestamp.to_string()),
+            Inline::Entity { utf8, .. } => text.push_str(utf8),
+            Inline::FootnoteReference { .. } => {}
+            Inline::LineBreak => text.push('\n'),
+        }
+    }
+    text
+}
+
+/// parse an object starting with `ch` at `i`.
+/// returns the object and the length of its text.
+fn parse_object(text: &str, i: usize, ch: char) -> Option<(Inline<'_>, usize)> {
+    let rem = &text[i..];
+    let previous = text[..i].chars().next_back();
+    match ch {
+        '*' | '/' | '_' | '+' | '=' | '~' if is_pre(previous) => parse_emphasis(rem, ch),
+        '_' | '^' if previous.is_some_and(|ch| !ch.is_whitespace()) => parse_script(rem),
+        '[' if rem.starts_with("[fn:") => parse_footnote_reference(rem),
+        '[' | '<' => Link::parse_prefix(rem)
+            .map(|(link, len)| (Inline::Link(link), len))
+            .or_else(|| {
+                let (timestamp, after) = Timestamp::parse_prefix(rem)?;
+                Some((Inline::Timestamp(timestamp), rem.len() - after.len()))
+            }),
+        '\\' => parse_backslash(rem),
+        '$' if previous != Some('$') => parse_dollar_fragment(rem),
+        ch if ch.is_ascii_lowercase() && !previous.is_some_and(char::is_alphanumeric) => {
+            Link::parse_prefix(rem).map(|(link, len)| (Inline::Link(link), len))
+        }
+        _ => None,
+    }
+}
+
+fn is_pre(previous: Option<char>) -> bool {
+    match previous {
+        None => true,
+        Some(ch) => ch.is_whitespace() || PRE.contains(ch),
+    }
+}
+
+fn is_post(next: Option<char>) -> bool {
+    match next {
+        None => true,
+        Some(ch) => ch.is_whitespace() || POST.contains(ch),
+    }
+}
+
+/// parse `*bold*` and the other emphasis markers at the start of `text`
+fn parse_emphasis(text: &str, marker: char) -> Option<(Inline<'_>, usize)> {
+    let body = &text[1..];
+    if body.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    let end = body.char_indices().skip(1).find_map(|(i, ch)| {
+        let previous = body[..i].chars().next_back()?;
+        let next = body[i + 1..].chars().next();
+        if ch == marker && !previous.is_whitespace() && is_post(next) {
+            Some(i)
+        } else {
+            None
+        }
+    })?;
+
+    let body = &body[..end];
+    // emphasis can span at most two lines
+    if body.matches('\n').count() > 1 {
+        return None;
+    }
+
+    let object = match marker {
+        '*' => Inline::Bold(parse_inline(body)),
+        '/' => Inline::Italic(parse_inline(body)),
+        '_' => Inline::Underline(parse_inline(body)),
+        '+' => Inline::StrikeThrough(parse_inline(body)),
+        '=' => Inline::Verbatim(body),
+        _ => Inline::Code(body),
+    };
+    Some((object, end + 2))
+}
+
+/// parse `_sub`, `^super`, `_{sub}` or `^{super}` at the start of `text`
+fn parse_script(text: &str) -> Option<(Inline<'_>, usize)> {
+    let superscript = text.starts_with('^');
+    let rem = &text[1..];
+
+    let (objects, braces, len) = if rem.starts_with('{') {
+        let end = matching_bracket(rem, '{', '}')?;
+        (parse_inline(&rem[1..end]), true, end + 2)
+    } else {
+        let sign = usize::from(rem.starts_with(['+', '-']));
+        let body_len = rem[sign..]
+            .find(|ch: char| !(ch.is_alphanumeric() || matches!(ch, ',' | '.' | '\\')))
+            .unwrap_or(rem.len() - sign);
+        let body = rem[sign..sign + body_len].trim_end_matches(|ch: char| !ch.is_alphanumeric());
+        if body.is_empty() {
+            return None;
+        }
+        let script = &rem[..sign + body.len()];
+        (vec![Inline::Text(script)], false, script.len() + 1)
+    };
+
+    let object = if superscript {
+        Inline::Superscript { objects, braces }
+    } else {
+        Inline::Subscript { objects, braces }
+    };
+    Some((object, len))
+}
+
+/// parse `[fn:label]`, `[fn:label:definition]` or `[fn::definition]`
+fn parse_footnote_reference(text: &str) -> Option<(Inline<'_>, usize)> {
+    let end = matching_bracket(text, '[', ']')?;
+    let inner = &text["[fn:".len()..end];
+    let (label, definition) = match inner.split_once(':') {
+        Some((label, definition)) => (label, Some(parse_inline(definition))),
+        None => (inner, None),
+    };
+
+    let label_is_valid = label.chars().all(is_label_char);
+    if !label_is_valid || (label.is_empty() && definition.is_none()) {
+        return None;
+    }
+
+    let label = Some(label).filter(|label| !label.is_empty());
+    Some((Inline::FootnoteReference { label, definition }, end + 1))
+}
+
+/// parse line breaks, entities and LaTeX fragments starting with `\`
+fn parse_backslash(text: &str) -> Option<(Inline<'_>, usize)> {
+    if let Some(rem) = text.strip_prefix("\\\\") {
+        let trailing = rem.len() - rem.trim_start_matches([' ', '\t']).len();
+        let rem = &rem[trailing..];
+        if rem.is_empty() || rem.starts_with('\n') {
+            return Some((Inline::LineBreak, 2 + trailing));
+        }
+        return None;
+    }
+
+    for (open, close) in [("\\(", "\\)"), ("\\[", "\\]")] {
+        if text.starts_with(open) {
+            let end = text[2..].find(close)? + 2 + close.len();
+            return Some((Inline::LatexFragment(&text[..end]), end));
+        }
+    }
+
+    let name_len = text[1..]
+        .find(|ch: char| !ch.is_ascii_alphabetic())
+        .unwrap_or(text.len() - 1);
+    if name_len == 0 {
+        return None;
+    }
+    let name = &text[1..1 + name_len];
+    let rem = &text[1 + name_len..];
+
+    if let Some((_, utf8)) = ENTITIES.iter().find(|(entity, _)| *entity == name) {
+        let braces = rem.starts_with("{}");
+        let len = 1 + name_len + if braces { 2 } else { 0 };
+        return Some((Inline::Entity { name, utf8, braces }, len));
+    }
+
+    // a LaTeX command with optional `[...]` and `{...}` arguments
+    let mut len = 1 + name_len;
+    while let Some(open) = text[len..]
+        .chars()
+        .next()
+        .filter(|ch| *ch == '[' || *ch == '{')
+    {
+        let close = if open == '[' { ']' } else { '}' };
+        len += matching_bracket(&text[len..], open, close)? + 1;
+    }
+    Some((Inline::LatexFragment(&text[..len]), len))
+}
+
+/// parse `$x$` or `$$x$$` at the start of `text`
+fn parse_dollar_fragment(text: &str) -> Option<(Inline<'_>, usize)> {
+    if let Some(rem) = text.strip_prefix("$$") {
+        let end = rem.find("$$")? + 4;
+        return Some((Inline::LatexFragment(&text[..end]), end));
+    }
+
+    let body = &text[1..];
+    let first = body.chars().next()?;
+    if first.is_whitespace() || ".,;$".contains(first) {
+        return None;
+    }
+    let end = body.char_indices().find_map(|(i, ch)| {
+        let previous = body[..i].chars().next_back();
+        let next = body[i + 1..].chars().next();
+        let closes = ch == '$'
+            && (i == 0 || previous.is_some_and(|ch| !ch.is_whitespace() && !".,$".contains(ch)))
+            && next.map_or(true, |ch| ch.is_whitespace() || ch.is_ascii_punctuation());
+        if closes && i > 0 {
+            Some(i)
+        } else {
+            None
+        }
+    })?;
+    Some((Inline::LatexFragment(&text[..end + 2]), end + 2))
+}
+
+/// the index of the bracket matching the opening bracket at the start of `text`
+fn matching_bracket(text: &str, open: char, close: char) -> Option<usize> {
+    let mut depth = 0;
+    for (i, ch) in text.char_indices() {
+        if ch == open {
+            depth += 1;
+        } else if ch == close {
+            depth -= 1;
+            if depth == 0 {
+                return Some(i);
+            }
+        } else if ch == '\n' {
+            return None;
+        }
+    }
+    None
+}
+
+fn write_objects(f: &mut fmt::Formatter<'_>, objects: &[Inline]) -> fmt::Result {
+    for object in objects {
+        write!(f, "{}", object)?;
+    }
+    Ok(())
+}
+
+fn write_script(
+    f: &mut fmt::Formatter<'_>,
+    marker: char,
+    objects: &[Inline],
+    braces: bool,
+) -> fmt::Result {
+    if braces {
+        write!(f, "{}{{", marker)?;
+        write_objects(f, objects)?;
+        write!(f, "}}")
+    } else {
+        write!(f, "{}", marker)?;
+        write_objects(f, objects)
+    }
+}
+
+/// formats the object as org text
+impl<'t> Display for Inline<'t> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let emphasis = |f: &mut fmt::Formatter<'_>, marker: char, objects: &[Inline]| {
+            write!(f, "{}", marker)?;
+            write_objects(f, objects)?;
+            write!(f, "{}", marker)
+        };
+
+        match self {
+            Inline::Text(text) | Inline::LatexFragment(text) => write!(f, "{}", text),
+            Inline::Bold(objects) => emphasis(f, '*', objects),
+            Inline::Italic(objects) => emphasis(f, '/', objects),
+            Inline::Underline(objects) => emphasis(f, '_', objects),
+            Inline::StrikeThrough(objects) => emphasis(f, '+', objects),
+            Inline::Verbatim(text) => write!(f, "={}=", text),
+            Inline::Code(text) => write!(f, "~{}~", text),
+            Inline::Link(link) => write!(f, "{}", link),
+            Inline::Timestamp(timestamp) => write!(f, "{}", timestamp),
+            Inline::Entity { name, braces, .. } => {
+                write!(f, "\\{}", name)?;
+                if *braces {
+                    write!(f, "{{}}")?;
+                }
+                Ok(())
+            }
+            Inline::FootnoteReference { label, definition } => {
+                write!(f, "[fn:{}", label.unwrap_or(""))?;
+                if let Some(definition) = definition {
+                    write!(f, ":")?;
+                    write_objects(f, definition)?;
+                }
+                write!(f, "]")
+            }
+            Inline::Subscript { objects, braces } => write_script(f, '_', objects, *braces),
+            Inline::Superscript { objects, braces } => write_script(f, '^', objects, *braces),
+            Inline::LineBreak => write!(f, "\\\\"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(text: &str) -> String {
+        parse_inline(text)
+            .iter()
+            .map(|object| object.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn parse_emphasis() {
+        let objects = parse_inline("some *bold /and italic/* text");
+        assert_eq!(
+            objects,
+            vec![
+                Inline::Text("some "),
+                Inline::Bold(vec![
+                    Inline::Text("bold "),
+                    Inline::Italic(vec![Inline::Text("and italic")])
+                ]),
+                Inline::Text(" text"),
+            ]
+        );
+
+        assert_eq!(
+            parse_inline("(=verbatim *not bold*=), ~code~."),
+            vec![
+                Inline::Text("("),
+                Inline::Verbatim("verbatim *not bold*"),
+                Inline::Text("), "),
+                Inline::Code("code"),
+                Inline::Text("."),
+            ]
+        );
+
+        assert_eq!(
+            parse_inline("_under_ and +strike+"),
+            vec![
+                Inline::Underline(vec![Inline::Text("under")]),
+                Inline::Text(" and "),
+                Inline::StrikeThrough(vec![Inline::Text("strike")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn emphasis_rules() {
+        for text in [
+            "a*b*c",
+            "* not bold*",
+            "*not bold *",
+            "*not bold*x",
+            "2*3*4",
+            "a/b/c",
+            "=unterminated",
+        ] {
+            assert_eq!(parse_inline(text), vec![Inline::Text(text)], "{}", text);
+        }
+    }
+
+    #[test]
+    fn parse_objects() {
+        let objects = parse_inline(
+            r"see [[https://orgmode.org][org]] on <2020-02-28 Fri> \alpha{} \rarr x[fn:1]\\",
+        );
+        assert!(matches!(objects[1], Inline::Link(link) if link.description == Some("org")));
+        assert!(matches!(objects[3], Inline::Timestamp(_)));
+        assert_eq!(
+            objects[5],
+            Inline::Entity {
+                name: "alpha",
+                utf8: "α",
+                braces: true
+            }
+        );
+        assert!(matches!(objects[7], Inline::Entity { name: "rarr", .. }));
+        assert_eq!(
+            objects[9],
+            Inline::FootnoteReference {
+                label: Some("1"),
+                definition: None
+            }
+        );
+        assert_eq!(objects[10], Inline::LineBreak);
+
+        assert_eq!(
+            parse_inline("[fn::an *inline* note]"),
+            vec![Inline::FootnoteReference {
+                label: None,
+                definition: Some(vec![
+                    Inline::Text("an "),
+                    Inline::Bold(vec![Inline::Text("inline")]),
+                    Inline::Text(" note"),
+                ])
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_latex_and_scripts() {
+        assert_eq!(
+            parse_inline(r"$x^2$ and \(y\), \frac{1}{2} costs $5"),
+            vec![
+                Inline::LatexFragment("$x^2$"),
+                Inline::Text(" and "),
+                Inline::LatexFragment(r"\(y\)"),
+                Inline::Text(", "),
+                Inline::LatexFragment(r"\frac{1}{2}"),
+                Inline::Text(" costs $5"),
+            ]
+        );
+
+        assert_eq!(
+            parse_inline("H_2O and e^{i*pi}"),
+            vec![
+                Inline::Text("H"),
+                Inline::Subscript {
+                    objects: vec![Inline::Text("2O")],
+                    braces: false
+                },
+                Inline::Text(" and e"),
+                Inline::Superscript {
+                    objects: vec![Inline::Text("i*pi")],
+                    braces: true
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn inline_round_trip() {
+        for text in [
+            "some *bold /and italic/* text",
+            r"H_2O and e^{i*pi}, \alpha{} \to [fn:note:with =code=]\\",
+            "see [[file:notes.org::*tasks][the tasks]] and https://example.com.",
+            "$x$ and \\[y\\]",
+        ] {
+            assert_eq!(round_trip(text), text);
+        }
+        assert_eq!(
+            plain_text(&parse_inline("*bold* \\alpha x_{2}")),
+            "bold α x2"
+        );
+    }
+}
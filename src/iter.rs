@@ -1,4 +1,6 @@
-use crate::{headline::HeadlineGroup, object::Object};
+use std::slice;
+
+use crate::{content::Content, headline::HeadlineGroup, object::Object};
 
 pub struct SubObjects<'t> {
     headline_group: &'t HeadlineGroup<'t>,
@@ -72,14 +74,31 @@ impl<'t> From<&'t Option<Vec<HeadlineGroup<'t>>>> for SubHeadlines<'t> {
     }
 }
 
+/// iterates over the children of either a headline or content
+enum ChildObjects<'t> {
+    Headline(SubObjects<'t>),
+    Content(slice::Iter<'t, Content<'t>>),
+}
+
+impl<'t> Iterator for ChildObjects<'t> {
+    type Item = Object<'t>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            ChildObjects::Headline(sub_objects) => sub_objects.next(),
+            ChildObjects::Content(contents) => contents.next().map(Object::from),
+        }
+    }
+}
+
 pub struct AllObjects<'a> {
-    stack: Vec<SubObjects<'a>>,
+    stack: Vec<ChildObjects<'a>>,
 }
 
 impl<'t> AllObjects<'t> {
     pub fn new(root: &'t HeadlineGroup<'t>) -> AllObjects<'t> {
         AllObjects {
-            stack: vec![root.into_iter()],
+            stack: vec![ChildObjects::Headline(root.into_iter())],
         }
     }
 }
@@ -109,12 +128,14 @@ impl<'a> Iterator for Headlines<'a> {
     }
 }
 
+#[allow(dead_code)]
 trait Node {
     type Data;
 
     fn data(&self) -> Self::Data;
 }
 
+#[allow(dead_code)]
 trait Tree {
     type Node: Node;
     type SubNodes: Iterator<Item = Self::Node>;
@@ -124,12 +145,18 @@ trait Tree {
     fn all_nodes(&self) -> Self::AllNodes;
 }
 
-fn find_next_object<'a>(stack: &mut Vec<SubObjects<'a>>) -> Option<Object<'a>> {
+fn find_next_object<'a>(stack: &mut Vec<ChildObjects<'a>>) -> Option<Object<'a>> {
     while let Some(iterator) = stack.last_mut() {
         match iterator.next() {
             Some(object) => {
-                if let Object::Headline(headline_group) = object {
-                    stack.push(headline_group.sub_objects());
+                match object {
+                    Object::Headline(headline_group) => {
+                        stack.push(ChildObjects::Headline(headline_group.sub_objects()))
+                    }
+                    Object::Content(content) if !content.children().is_empty() => {
+                        stack.push(ChildObjects::Content(content.children().iter()))
+                    }
+                    _ => {}
                 }
                 return Some(object);
             }
@@ -0,0 +1,89 @@
+use std::{fmt, fmt::Display};
+
+/// a `#+KEY: value` line, e.g. `#+TITLE: my notes`
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Keyword<'t> {
+    pub key: &'t str,
+    pub value: &'t str,
+    /// the line the keyword was parsed from
+    pub raw: Option<&'t str>,
+}
+
+impl<'t> Keyword<'t> {
+    /// parse a keyword line.
+    /// returns `None` if the line is not a keyword.
+    pub fn parse(line: &'t str) -> Option<Keyword<'t>> {
+        let rem = line.trim().strip_prefix("#+")?;
+        let end = rem.find(':')?;
+        let (key, value) = rem.split_at(end);
+
+        if key.is_empty() || key.contains(char::is_whitespace) {
+            return None;
+        }
+
+        Some(Keyword {
+            key,
+            value: value[1..].trim(),
+            raw: Some(line),
+        })
+    }
+
+    /// check the key, ignoring case
+    pub fn is(&self, key: &str) -> bool {
+        self.key.eq_ignore_ascii_case(key)
+    }
+}
+
+impl<'t> Display for Keyword<'t> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // print the line as written unless the keyword was changed
+        let parsed = self.raw.and_then(Keyword::parse);
+        if let Some(raw) = self
+            .raw
+            .filter(|_| parsed.map(|k| (k.key, k.value)) == Some((self.key, self.value)))
+        {
+            return write!(f, "{}", raw);
+        }
+        if self.value.is_empty() {
+            write!(f, "#+{}:", self.key)
+        } else {
+            write!(f, "#+{}: {}", self.key, self.value)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_keywords() {
+        assert_eq!(
+            Keyword::parse("#+TITLE: org: a portable, fast library"),
+            Some(Keyword {
+                key: "TITLE",
+                value: "org: a portable, fast library",
+                raw: Some("#+TITLE: org: a portable, fast library"),
+            })
+        );
+        assert_eq!(
+            Keyword::parse("  #+options:"),
+            Some(Keyword {
+                key: "options",
+                value: "",
+                raw: Some("  #+options:"),
+            })
+        );
+        assert_eq!(Keyword::parse("#+BEGIN_SRC rust"), None);
+        let keyword = Keyword::parse("#+TITLE:no space").unwrap();
+        assert_eq!((keyword.key, keyword.value), ("TITLE", "no space"));
+        assert_eq!(format!("{}", keyword), "#+TITLE:no space");
+        let keyword = Keyword {
+            value: "changed",
+            ..keyword
+        };
+        assert_eq!(format!("{}", keyword), "#+TITLE: changed");
+        assert_eq!(Keyword::parse("#+: value"), None);
+        assert_eq!(Keyword::parse("# a comment"), None);
+    }
+}
@@ -1,9 +1,26 @@
+pub mod agenda;
+pub mod clock;
+pub mod clocktable;
 pub mod config;
+pub mod content;
+pub mod dynamic_block;
 pub mod error;
+pub mod footnote;
+pub mod inline;
 pub mod iter;
+pub mod keyword;
+pub mod link;
 pub mod object;
 pub mod parser;
+pub mod priority;
+pub mod query;
 pub mod status_labels;
+pub mod table;
+pub mod tags;
+pub mod timestamp;
 
-mod content;
+mod block;
 mod headline;
+mod planning;
+mod properties;
+mod statistics;
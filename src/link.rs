@@ -0,0 +1,257 @@
+use std::{fmt, fmt::Display, ops::Range};
+
+/// link types recognized in angle and plain links
+const LINK_TYPES: [&str; 18] = [
+    "attachment",
+    "bbdb",
+    "doi",
+    "docview",
+    "elisp",
+    "file",
+    "ftp",
+    "gnus",
+    "help",
+    "http",
+    "https",
+    "id",
+    "info",
+    "irc",
+    "mailto",
+    "news",
+    "rmail",
+    "shell",
+];
+
+/// a link, e.g. `[[https://orgmode.org][org]]`, `<id:1234>` or `file:notes.org::*tasks`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Link<'t> {
+    pub format: LinkFormat,
+    /// the link type, e.g. `https` or `file`.
+    /// bracket links without a type are `file` links if the target is a path,
+    /// `custom-id` links for `#id`, `coderef` links for `(ref)`
+    /// and `fuzzy` links otherwise, e.g. `[[*heading]]`
+    pub link_type: &'t str,
+    /// the target without the type or search option
+    pub path: &'t str,
+    /// the search option of a file link, e.g. `*heading` in `file:notes.org::*heading`
+    pub search_option: Option<&'t str>,
+    pub description: Option<&'t str>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LinkFormat {
+    /// `[[target][description]]` or `[[target]]`
+    Bracket,
+    /// `<type:path>`
+    Angle,
+    /// `type:path`
+    Plain,
+}
+
+impl<'t> Link<'t> {
+    /// parse a link at the start of `text`.
+    /// returns the link and the length of its text.
+    pub fn parse_prefix(text: &'t str) -> Option<(Link<'t>, usize)> {
+        if text.starts_with("[[") {
+            parse_bracket_link(text)
+        } else if let Some(rem) = text.strip_prefix('<') {
+            let end = rem.find(['<', '>', '\n'])?;
+            if !rem[end..].starts_with('>') {
+                return None;
+            }
+            let (link_type, path) = split_type(&rem[..end])?;
+            let link = Link::new(LinkFormat::Angle, link_type, path.trim(), None);
+            Some((link, end + 2))
+        } else {
+            let (link_type, path) = split_type(text)?;
+            let end = path
+                .find(|ch: char| ch.is_whitespace() || "()<>[]\"'".contains(ch))
+                .unwrap_or(path.len());
+            let path = path[..end].trim_end_matches(|ch| ".,;:!?".contains(ch));
+            if path.is_empty() {
+                return None;
+            }
+            let link = Link::new(LinkFormat::Plain, link_type, path, None);
+            Some((link, link_type.len() + 1 + path.len()))
+        }
+    }
+
+    fn new(
+        format: LinkFormat,
+        link_type: &'t str,
+        path: &'t str,
+        description: Option<&'t str>,
+    ) -> Link<'t> {
+        let (path, search_option) = match path.find("::") {
+            Some(i) if link_type == "file" => (&path[..i], Some(&path[i + 2..])),
+            _ => (path, None),
+        };
+        Link {
+            format,
+            link_type,
+            path,
+            search_option,
+            description,
+        }
+    }
+
+    /// the target as written in a link, e.g. `file:notes.org::*tasks`
+    pub fn target(&self) -> String {
+        let mut target = match self.link_type {
+            "fuzzy" | "custom-id" | "coderef" => self.path.to_string(),
+            link_type => format!("{}:{}", link_type, self.path),
+        };
+        if let Some(search_option) = self.search_option {
+            target.push_str("::");
+            target.push_str(search_option);
+        }
+        target
+    }
+}
+
+fn parse_bracket_link(text: &str) -> Option<(Link<'_>, usize)> {
+    let rem = &text[2..];
+    let target_end = rem.find(']')?;
+    let target = &rem[..target_end];
+    if target.is_empty() || target.contains('[') {
+        return None;
+    }
+
+    let after_target = &rem[target_end..];
+    let (description, len) = if after_target.starts_with("]]") {
+        (None, 2 + target_end + 2)
+    } else if let Some(description) = after_target.strip_prefix("][") {
+        let end = description.find("]]")?;
+        (Some(&description[..end]), 2 + target_end + 2 + end + 2)
+    } else {
+        return None;
+    };
+
+    let (link_type, path) = match split_type(target) {
+        Some((link_type, path)) => (link_type, path),
+        None if target.starts_with(['/', '~']) || target.starts_with("./") => ("file", target),
+        None if target.starts_with("../") => ("file", target),
+        None if target.starts_with('#') => ("custom-id", target),
+        None if target.starts_with('(') && target.ends_with(')') => ("coderef", target),
+        None => ("fuzzy", target),
+    };
+
+    let link = Link::new(LinkFormat::Bracket, link_type, path, description);
+    Some((link, len))
+}
+
+/// split a `type:path` target if `type` is a known link type
+fn split_type(target: &str) -> Option<(&str, &str)> {
+    let end = target.find(|ch: char| !ch.is_ascii_alphabetic())?;
+    let (link_type, path) = target.split_at(end);
+    match path.strip_prefix(':') {
+        Some(path) if LINK_TYPES.contains(&link_type) => Some((link_type, path)),
+        _ => None,
+    }
+}
+
+/// find all links in `text`
+pub fn find_links(text: &str) -> Links<'_> {
+    Links { text, offset: 0 }
+}
+
+/// an iterator over the links in some text
+/// and their byte ranges in the text
+pub struct Links<'t> {
+    text: &'t str,
+    offset: usize,
+}
+
+impl<'t> Iterator for Links<'t> {
+    type Item = (Range<usize>, Link<'t>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut previous: Option<char> = self.text[..self.offset].chars().next_back();
+        while let Some(ch) = self.text[self.offset..].chars().next() {
+            let start = self.offset;
+            // plain links start at a word boundary
+            let at_boundary = !previous.is_some_and(char::is_alphanumeric);
+            let candidate = matches!(ch, '[' | '<') || (at_boundary && ch.is_ascii_lowercase());
+            if candidate {
+                if let Some((link, len)) = Link::parse_prefix(&self.text[start..]) {
+                    self.offset = start + len;
+                    return Some((start..start + len, link));
+                }
+            }
+            self.offset += ch.len_utf8();
+            previous = Some(ch);
+        }
+        None
+    }
+}
+
+impl<'t> Display for Link<'t> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.format, self.description) {
+            (LinkFormat::Bracket, Some(description)) => {
+                write!(f, "[[{}][{}]]", self.target(), description)
+            }
+            (LinkFormat::Bracket, None) => write!(f, "[[{}]]", self.target()),
+            (LinkFormat::Angle, _) => write!(f, "<{}>", self.target()),
+            (LinkFormat::Plain, _) => write!(f, "{}", self.target()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn links(text: &str) -> Vec<Link<'_>> {
+        find_links(text).map(|(_, link)| link).collect()
+    }
+
+    #[test]
+    fn parse_bracket_links() {
+        let link = links("see [[https://orgmode.org][the org site]].")[0];
+        assert_eq!(link.format, LinkFormat::Bracket);
+        assert_eq!(link.link_type, "https");
+        assert_eq!(link.path, "//orgmode.org");
+        assert_eq!(link.description, Some("the org site"));
+        assert_eq!(format!("{}", link), "[[https://orgmode.org][the org site]]");
+
+        let link = links("[[file:notes.org::*tasks]]")[0];
+        assert_eq!(link.link_type, "file");
+        assert_eq!(link.path, "notes.org");
+        assert_eq!(link.search_option, Some("*tasks"));
+        assert_eq!(format!("{}", link), "[[file:notes.org::*tasks]]");
+
+        let link = links("[[./notes.org::123]]")[0];
+        assert_eq!((link.link_type, link.path), ("file", "./notes.org"));
+        assert_eq!(link.search_option, Some("123"));
+
+        let types: Vec<_> = links("[[*heading]] [[#custom]] [[(ref)]] [[some words]]")
+            .iter()
+            .map(|link| link.link_type)
+            .collect();
+        assert_eq!(types, vec!["fuzzy", "custom-id", "coderef", "fuzzy"]);
+
+        assert!(links("[[unterminated] and [X] checkbox").is_empty());
+    }
+
+    #[test]
+    fn parse_angle_and_plain_links() {
+        let text = "<https://example.com/a b> or id:1234-abcd, mailto:me@example.com.";
+        let found: Vec<_> = find_links(text).collect();
+        assert_eq!(found.len(), 3);
+
+        let (range, link) = found[0].clone();
+        assert_eq!(&text[range], "<https://example.com/a b>");
+        assert_eq!(link.format, LinkFormat::Angle);
+        assert_eq!(link.path, "//example.com/a b");
+
+        let (range, link) = found[1].clone();
+        assert_eq!(&text[range], "id:1234-abcd");
+        assert_eq!((link.link_type, link.path), ("id", "1234-abcd"));
+
+        let (_, link) = &found[2];
+        assert_eq!(link.path, "me@example.com");
+
+        assert!(links("<2020-02-28 Fri> and notid:1234 and https: alone").is_empty());
+    }
+}
@@ -1,14 +1,43 @@
-use std::{fmt, fmt::Display};
+use std::{borrow::Cow, collections::HashSet, fmt, fmt::Display, io, ops::Range};
 
 use crate::{
     content::Content,
+    dynamic_block::BlockWriters,
+    error::{self, Diagnostic, OrgError},
+    footnote::{footnote_references, Footnote, FootnoteDefinition, Footnotes},
     headline::HeadlineGroup,
+    inline::{parse_inline, Inline},
+    keyword::Keyword,
+    link::{find_links, Link},
+    priority::Priorities,
+    query::Query,
+    status_labels::StatusLabels,
+    tags::TagGroups,
 };
 
 #[derive(Debug)]
 pub struct Document<'t> {
     pub text: &'t str,
     pub root: HeadlineGroup<'t>,
+    /// the status labels of the headlines.
+    /// these are the todo sequences defined with `#+TODO:` lines in the text, if any.
+    pub status_labels: StatusLabels,
+    /// the tags set with `#+FILETAGS:`, inherited by every headline
+    pub file_tags: Vec<&'t str>,
+    /// the group tags declared with `#+TAGS:`
+    pub tag_groups: TagGroups<'t>,
+    /// the priorities of a document without a `#+PRIORITIES:` line
+    pub default_priorities: Priorities,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// a location in the text of a document
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+    /// the line number, starting from 0
+    pub line: usize,
+    /// the byte range in the text
+    pub range: Range<usize>,
 }
 
 #[derive(Debug)]
@@ -22,6 +51,239 @@ impl<'t> Document<'t> {
         self.root.all_headlines()
     }
 
+    /// all headlines that match `query`
+    pub fn search(&'t self, query: &'t Query) -> impl Iterator<Item = &'t HeadlineGroup<'t>> {
+        query.search(self)
+    }
+
+    /// recompute all statistics cookies in the document.
+    /// see `HeadlineGroup::update_statistics`
+    pub fn update_statistics(&mut self) {
+        self.root.update_statistics(&self.status_labels);
+    }
+
+    /// evaluate the `#+TBLFM:` formulas of all tables in the document.
+    /// returns diagnostics for the formulas that could not be applied.
+    pub fn recalculate_tables(&mut self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        self.root.recalculate_tables(&mut diagnostics);
+        diagnostics
+    }
+
+    /// regenerate the bodies of the dynamic blocks in the document
+    /// with the writer registered for their name.
+    /// returns diagnostics for blocks without a writer
+    /// and blocks that could not be written, which are left unchanged.
+    pub fn update_dynamic_blocks(&mut self, writers: &BlockWriters) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let mut bodies = Vec::new();
+        for block in self.root.dynamic_blocks() {
+            let body = match writers.get(block.name) {
+                Some(writer) => writer.write(block, self).map_err(|err| match err {
+                    OrgError::ParseError(_, message) | OrgError::Unexpected(message) => message,
+                    OrgError::IoError(err) => err.to_string(),
+                }),
+                None => Err("there is no writer for it".to_string()),
+            };
+            if let Err(message) = &body {
+                let message = format!("dynamic block `{}` not updated: {}", block.name, message);
+                diagnostics.push(Diagnostic::new(self.line(block.name), message));
+            }
+            bodies.push(body.ok());
+        }
+
+        for (block, body) in self.root.dynamic_blocks_mut().into_iter().zip(bodies) {
+            if let Some(body) = body {
+                block.set_body(&body);
+            }
+        }
+        diagnostics
+    }
+
+    /// write the document as org text,
+    /// e.g. back to its file after updating it
+    pub fn write_to<W: io::Write>(&self, mut writer: W) -> error::Result<()> {
+        write!(writer, "{}", self)?;
+        Ok(())
+    }
+
+    /// the text in the document that can contain inline objects,
+    /// i.e. headline titles, keyword values, text and list items
+    pub fn inline_text(&'t self) -> impl Iterator<Item = &'t str> {
+        self.inline_text_by_line().map(|(text, _)| text)
+    }
+
+    /// the inline text with the lines of table cells,
+    /// see `Content::inline_text_by_line`
+    fn inline_text_by_line(&'t self) -> impl Iterator<Item = (&'t str, Option<&'t str>)> {
+        self.objects().flat_map(|object| match object {
+            Object::Headline(headline) if headline.headline.level() > 0 => {
+                vec![(headline.headline.title(), None)]
+            }
+            Object::Headline(_) => Vec::new(),
+            Object::Content(content) => content.inline_text_by_line(),
+        })
+    }
+
+    /// all links in the document and their locations.
+    /// links in recalculated table cells are located at their row.
+    pub fn links(&'t self) -> impl Iterator<Item = (Location, Link<'t>)> {
+        self.inline_text_by_line().flat_map(move |(text, line)| {
+            let offset = self.offset(text);
+            let row = line.and_then(|line| self.offset(line).map(|start| (start, line.len())));
+            find_links(text).filter_map(move |(range, link)| match (offset, row) {
+                (Some(offset), _) => {
+                    let start = offset + range.start;
+                    Some((self.location(start..start + range.len()), link))
+                }
+                (None, Some((start, len))) => Some((self.location(start..start + len), link)),
+                (None, None) => None,
+            })
+        })
+    }
+
+    /// the footnote definitions at the start of lines, `[fn:label] text`
+    pub fn footnote_definitions(&'t self) -> impl Iterator<Item = &'t FootnoteDefinition<'t>> {
+        self.objects().filter_map(|object| match object {
+            Object::Content(Content::Footnote(definition)) => Some(definition),
+            _ => None,
+        })
+    }
+
+    /// all footnote references in the document, including inline footnotes.
+    /// each item is an `Inline::FootnoteReference`.
+    pub fn footnote_references(&'t self) -> Vec<Inline<'t>> {
+        self.inline_text()
+            .flat_map(|text| {
+                footnote_references(&parse_inline(text))
+                    .into_iter()
+                    .cloned()
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// the footnote definitions of the document by label.
+    /// build them once to resolve many references.
+    pub fn footnotes(&'t self) -> Footnotes<'t> {
+        Footnotes::new(self.footnote_definitions(), &self.footnote_references())
+    }
+
+    /// find the definition of the footnote `reference`.
+    /// inline footnotes are their own definition, labeled references are
+    /// resolved to a definition at the start of a line or an inline footnote
+    /// with the same label.
+    /// this searches the whole document, see `footnotes` to resolve many references.
+    pub fn resolve_footnote(&'t self, reference: &Inline<'t>) -> Option<Footnote<'t>> {
+        self.footnotes().resolve(reference)
+    }
+
+    /// warnings about references to footnotes that are not defined,
+    /// definitions that are never referenced and labels defined twice
+    pub fn footnote_diagnostics(&'t self) -> Vec<Diagnostic> {
+        let references = self.footnote_references();
+        let mut defined: HashSet<&str> = HashSet::new();
+        let mut diagnostics = Vec::new();
+
+        let definitions = self
+            .footnote_definitions()
+            .map(|definition| definition.label)
+            .chain(references.iter().filter_map(|reference| match reference {
+                Inline::FootnoteReference {
+                    label: Some(label),
+                    definition: Some(_),
+                } => Some(*label),
+                _ => None,
+            }));
+        for label in definitions {
+            if !defined.insert(label) {
+                let message = format!("footnote `{}` is defined more than once", label);
+                diagnostics.push(Diagnostic::new(self.line(label), message));
+            }
+        }
+
+        let mut referenced: HashSet<&str> = HashSet::new();
+        for reference in &references {
+            if let Inline::FootnoteReference {
+                label: Some(label),
+                definition: None,
+            } = reference
+            {
+                referenced.insert(label);
+                if !defined.contains(label) {
+                    let message = format!("footnote `{}` is not defined", label);
+                    diagnostics.push(Diagnostic::new(self.line(label), message));
+                }
+            }
+        }
+        for definition in self.footnote_definitions() {
+            if !referenced.contains(definition.label) {
+                let message = format!("footnote `{}` is never referenced", definition.label);
+                diagnostics.push(Diagnostic::new(self.line(definition.label), message));
+            }
+        }
+
+        diagnostics.sort_by_key(|diagnostic| diagnostic.line);
+        diagnostics
+    }
+
+    /// the location of a byte range in the text
+    pub fn location(&self, range: Range<usize>) -> Location {
+        let line = self.text[..range.start].matches('\n').count();
+        Location { line, range }
+    }
+
+    /// the line of `slice` in the text
+    fn line(&self, slice: &str) -> Option<usize> {
+        let start = self.offset(slice)?;
+        Some(self.location(start..start + slice.len()).line)
+    }
+
+    /// the byte offset of `slice` in the text,
+    /// or `None` if it is not a slice of the text
+    fn offset(&self, slice: &str) -> Option<usize> {
+        let start = self.text.as_ptr() as usize;
+        let slice_start = slice.as_ptr() as usize;
+        if slice_start >= start && slice_start + slice.len() <= start + self.text.len() {
+            Some(slice_start - start)
+        } else {
+            None
+        }
+    }
+
+    /// all `#+KEY: value` keywords in the document
+    pub fn keywords(&'t self) -> impl Iterator<Item = &'t Keyword<'t>> {
+        self.objects().filter_map(|object| match object {
+            Object::Content(Content::Keyword(keyword)) => Some(keyword),
+            _ => None,
+        })
+    }
+
+    /// the value of the first keyword with `key`, ignoring case
+    pub fn keyword(&'t self, key: &str) -> Option<&'t str> {
+        self.keywords()
+            .find(|keyword| keyword.is(key))
+            .map(|keyword| keyword.value)
+    }
+
+    /// the priority range set with `#+PRIORITIES:`, or `default_priorities`
+    pub fn priorities(&'t self) -> Priorities {
+        self.keyword("PRIORITIES")
+            .and_then(Priorities::parse)
+            .unwrap_or(self.default_priorities)
+    }
+
+    /// the `#+TITLE` of the document.
+    /// multiple `#+TITLE` lines are joined with spaces.
+    pub fn title(&'t self) -> Option<Cow<'t, str>> {
+        self.keywords()
+            .filter(|keyword| keyword.is("TITLE"))
+            .fold(None, |title, keyword| match title {
+                None => Some(Cow::from(keyword.value)),
+                Some(title) => Some(Cow::from(format!("{} {}", title, keyword.value))),
+            })
+    }
+
     pub fn objects(&'t self) -> impl Iterator<Item = Object<'t>> {
         self.root.all_objects()
     }
@@ -41,14 +303,260 @@ impl<'t> From<&'t Content<'t>> for Object<'t> {
 
 impl<'t> Display for Document<'t> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for headline in self.root.sub_headlines() {
-            write!(f, "{}", headline)?;
-        }
-        Ok(())
+        write!(f, "{}", self.root)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{inline::plain_text, parser::parse_org_text, table::Row};
+
+    const TEST_STATES: [&str; 3] = ["TODO", "STARTED", "DONE"];
+
+    const KEYWORD_TEXT: &str = "#+TITLE: org: a portable, fast library
+#+title: for your notes
+#+AUTHOR: covercash2
+some text
+* a headline
+#+NAME: a-keyword-in-a-section
+";
+
+    #[test]
+    fn document_keywords() {
+        let document = parse_org_text(KEYWORD_TEXT, TEST_STATES.to_vec()).unwrap();
+
+        assert_eq!(
+            document.title().as_deref(),
+            Some("org: a portable, fast library for your notes")
+        );
+        assert_eq!(document.keyword("author"), Some("covercash2"));
+        assert_eq!(document.keyword("NAME"), Some("a-keyword-in-a-section"));
+        assert_eq!(document.keyword("DATE"), None);
+        assert_eq!(document.keywords().count(), 4);
+        assert_eq!(format!("{}", document), KEYWORD_TEXT);
+    }
+
+    const LINK_TEXT: &str = "#+TITLE: see [[https://orgmode.org][org]]
+* TODO read [[file:notes.org::*tasks][the tasks]]
+- an item with <id:1234>
+  and a continuation with https://example.com
+:LOGBOOK:
+- note with [[#custom-id]]
+:END:
+#+BEGIN_SRC sh
+curl https://not-a-link.example.com
+#+END_SRC
+";
+
+    #[test]
+    fn document_links() {
+        let document = parse_org_text(LINK_TEXT, TEST_STATES.to_vec()).unwrap();
+        let links: Vec<_> = document
+            .links()
+            .map(|(location, link)| (location.line, &LINK_TEXT[location.range], link.link_type))
+            .collect();
+
+        assert_eq!(
+            links,
+            vec![
+                (0, "[[https://orgmode.org][org]]", "https"),
+                (1, "[[file:notes.org::*tasks][the tasks]]", "file"),
+                (2, "<id:1234>", "id"),
+                (3, "https://example.com", "https"),
+                (5, "[[#custom-id]]", "custom-id"),
+            ]
+        );
+    }
+
+    const TABLE_TEXT: &str = "* costs
+:DETAILS:
+| a | 2 |   |
+| b | 3 |   |
+#+TBLFM: $3=$2*10::@>$2=vsum(@1..@-1)
+:END:
+* elisp
+| 1 |
+#+TBLFM: $1='(+ 1 1)
+";
+
+    #[test]
+    fn recalculate_tables() {
+        let mut document = parse_org_text(TABLE_TEXT, TEST_STATES.to_vec()).unwrap();
+        let diagnostics = document.recalculate_tables();
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].message.contains("elisp"));
+        assert_eq!(diagnostics[0].line, Some(8));
+        assert!(format!("{}", document).contains("| b | 2 | 30 |"));
+        assert!(document.inline_text().any(|text| text == "30"));
+    }
+
+    #[test]
+    fn links_in_changed_cells() {
+        let mut document = parse_org_text("* links\n| a | b |\n", TEST_STATES.to_vec()).unwrap();
+        let headline = document.root.sub_headlines.as_mut().unwrap();
+        if let Some(Content::Table(table)) = headline[0].content.as_mut().unwrap().first_mut() {
+            if let Row::Cells(cells) = &mut table.rows[0] {
+                cells[1] = "[[https://example.com]][fn:1]".to_string().into();
+            }
+        }
+
+        let links: Vec<_> = document.links().map(|(location, _)| location).collect();
+        assert_eq!(
+            links,
+            vec![Location {
+                line: 1,
+                range: 8..17
+            }]
+        );
+        assert_eq!(document.footnote_references().len(), 1);
+    }
+
+    const FOOTNOTE_TEXT: &str = "* notes
+text with a note[fn:1] and an inline note[fn:inline:defined *here*].
+see also [fn:inline] and [fn:missing] and [fn::an anonymous note].
+
+[fn:1] the first note
+  continues here
+- with a list
+
+
+[fn:unused] never referenced
+";
+
+    #[test]
+    fn footnotes() {
+        let document = parse_org_text(FOOTNOTE_TEXT, TEST_STATES.to_vec()).unwrap();
+        assert_eq!(format!("{}", document), FOOTNOTE_TEXT);
+        assert_eq!(document.footnote_references().len(), 5);
+
+        let definition = document.footnote_definitions().next().unwrap();
+        assert_eq!(definition.label, "1");
+        assert_eq!(definition.contents.len(), 2);
+
+        let footnotes = document.footnotes();
+        let resolved: Vec<_> = document
+            .footnote_references()
+            .iter()
+            .map(|reference| match footnotes.resolve(reference) {
+                Some(Footnote::Definition(definition)) => definition.content.to_string(),
+                Some(Footnote::Inline(objects)) => plain_text(&objects),
+                None => "none".to_string(),
+            })
+            .collect();
+        assert_eq!(
+            resolved,
+            [
+                "the first note",
+                "defined here",
+                "defined here",
+                "none",
+                "an anonymous note"
+            ]
+        );
+        assert!(footnotes.get("inline").is_some());
+        assert!(footnotes.get("missing").is_none());
+        let reference = &document.footnote_references()[0];
+        assert!(matches!(
+            document.resolve_footnote(reference),
+            Some(Footnote::Definition(definition)) if definition.label == "1"
+        ));
+
+        let diagnostics: Vec<_> = document
+            .footnote_diagnostics()
+            .into_iter()
+            .map(|diagnostic| (diagnostic.line, diagnostic.message))
+            .collect();
+        assert_eq!(
+            diagnostics,
+            [
+                (Some(2), "footnote `missing` is not defined".to_string()),
+                (Some(9), "footnote `unused` is never referenced".to_string()),
+            ]
+        );
+    }
+
+    const DYNAMIC_TEXT: &str = "* report
+#+BEGIN: clocktable :maxlevel 1 :tstart \"<2020-02-28 Fri>\"
+- an old report
+#+END:
+#+BEGIN: tasks
+#+END:
+#+BEGIN: columnview :id local
+| kept |
+#+END:
+* TODO write report
+:LOGBOOK:
+CLOCK: [2020-02-27 Thu 09:00]--[2020-02-27 Thu 10:00] =>  1:00
+CLOCK: [2020-02-28 Fri 09:00]--[2020-02-28 Fri 10:30] =>  1:30
+:END:
+";
+
+    const UPDATED_DYNAMIC_TEXT: &str = "* report
+#+BEGIN: clocktable :maxlevel 1 :tstart \"<2020-02-28 Fri>\"
+| Headline     | Time   |
+|--------------+--------|
+| *Total time* | *1:30* |
+|--------------+--------|
+| write report | 1:30   |
+#+END:
+#+BEGIN: tasks
+- TODO write report
+#+END:
+#+BEGIN: columnview :id local
+| kept |
+#+END:
+* TODO write report
+:LOGBOOK:
+CLOCK: [2020-02-27 Thu 09:00]--[2020-02-27 Thu 10:00] =>  1:00
+CLOCK: [2020-02-28 Fri 09:00]--[2020-02-28 Fri 10:30] =>  1:30
+:END:
+";
+
+    #[test]
+    fn update_dynamic_blocks() {
+        use crate::{
+            clocktable::ClockTableWriter,
+            dynamic_block::{BlockWriters, DynamicBlock},
+            timestamp::Date,
+        };
+
+        let mut document = parse_org_text(DYNAMIC_TEXT, TEST_STATES.to_vec()).unwrap();
+        assert_eq!(format!("{}", document), DYNAMIC_TEXT);
+        let blocks = document.root.dynamic_blocks();
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[0].body, vec!["- an old report"]);
+
+        let tasks = |_: &DynamicBlock<'_>, document: &Document<'_>| {
+            let tasks: Vec<String> = document
+                .headlines()
+                .filter_map(|headline| {
+                    let status = headline.headline.status()?;
+                    Some(format!("- {} {}", status, headline.headline.title()))
+                })
+                .collect();
+            Ok(tasks.join("\n"))
+        };
+        let writers = BlockWriters::new()
+            .register(
+                "clocktable",
+                ClockTableWriter {
+                    today: Date::parse("2020-03-02").unwrap(),
+                },
+            )
+            .register("tasks", tasks);
+
+        let diagnostics = document.update_dynamic_blocks(&writers);
+        assert_eq!(
+            diagnostics,
+            [Diagnostic::new(
+                Some(6),
+                "dynamic block `columnview` not updated: there is no writer for it".to_string()
+            )]
+        );
+
+        let mut text = Vec::new();
+        document.write_to(&mut text).unwrap();
+        assert_eq!(String::from_utf8(text).unwrap(), UPDATED_DYNAMIC_TEXT);
+    }
 }
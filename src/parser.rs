@@ -1,12 +1,24 @@
+use std::borrow::Cow;
 use std::iter::Enumerate;
 use std::str::Lines;
 
 use super::{
+    block::Block,
+    clock::format_duration,
+    config::Config,
     content::{Bullet, Content, ListItem},
+    dynamic_block::DynamicBlock,
     error,
-    error::OrgError,
+    error::{Diagnostic, OrgError},
     headline::{Headline, HeadlineGroup},
+    keyword::Keyword,
     object::Document,
+    planning::Planning,
+    priority::Priorities,
+    properties::{Properties, Property, DRAWER_END, PROPERTIES},
+    status_labels::{Sequence, StatusLabels},
+    table::{Table, TBLFM},
+    tags::{parse_tag_list, TagGroups},
 };
 
 mod line;
@@ -14,20 +26,126 @@ use line::Line;
 
 type RawLine<'t> = (usize, &'t str);
 
-pub fn parse_org_text<'t, I: IntoIterator<Item = &'t str>>(
+pub fn parse_org_text<'t, L: Into<StatusLabels>>(
     text: &'t str,
-    status_labels: I,
+    status_labels: L,
 ) -> error::Result<Document<'t>> {
-    let labels: Vec<&str> = status_labels.into_iter().collect();
-    let mut cursor = OrgCursor::new(text, |raw_line| raw_line_to_line(raw_line, &labels))?;
-    let root: HeadlineGroup = parse_headline_objects(Headline::new_root(), &mut cursor, &labels)?;
-    Ok(Document { text, root })
+    parse(
+        text,
+        status_labels.into(),
+        Priorities::default(),
+        false,
+        &[],
+    )
+}
+
+/// parse `text` with the status labels, priorities, list and tag options in `config`
+pub fn parse_org_text_with_config<'t>(
+    text: &'t str,
+    config: &Config,
+) -> error::Result<Document<'t>> {
+    let exclude_tags: Vec<&str> = config
+        .tags_exclude_from_inheritance
+        .iter()
+        .map(String::as_str)
+        .collect();
+    parse(
+        text,
+        config.status_labels.clone(),
+        config.priorities,
+        config.allow_alphabetical_lists,
+        &exclude_tags,
+    )
+}
+
+fn parse<'t>(
+    text: &'t str,
+    status_labels: StatusLabels,
+    default_priorities: Priorities,
+    alphabetical_lists: bool,
+    exclude_tags: &[&str],
+) -> error::Result<Document<'t>> {
+    let mut sequences = Vec::new();
+    let mut file_tags = Vec::new();
+    let mut tag_groups = TagGroups::default();
+    let mut diagnostics = Vec::new();
+    for (line_number, keyword) in scan_keywords(text) {
+        if keyword.is("FILETAGS") {
+            file_tags.extend(parse_tag_list(keyword.value));
+        } else if keyword.is("TAGS") {
+            tag_groups.extend(keyword.value);
+        } else {
+            // invalid sequences are skipped with a warning
+            match Sequence::from_keyword(&keyword) {
+                Some(Ok(sequence)) => sequences.push(sequence),
+                Some(Err(err)) => diagnostics.push(Diagnostic::new(
+                    Some(line_number),
+                    format!("invalid todo sequence: {}", err),
+                )),
+                None => {}
+            }
+        }
+    }
+
+    let status_labels = if sequences.is_empty() {
+        status_labels
+    } else {
+        StatusLabels::new(sequences)
+    };
+    let labels: Vec<&str> = status_labels.iter().collect();
+    let mut cursor = OrgCursor::new(text, |raw_line| {
+        raw_line_to_line(raw_line, &labels, alphabetical_lists)
+    })?;
+    cursor.diagnostics = diagnostics;
+    let mut root: HeadlineGroup = parse_headline_objects(Headline::new_root(), &mut cursor)?;
+    root.inherit_tags(&file_tags, exclude_tags);
+    let diagnostics = cursor.diagnostics;
+    Ok(Document {
+        text,
+        root,
+        status_labels,
+        file_tags,
+        tag_groups,
+        default_priorities,
+        diagnostics,
+    })
+}
+
+/// find the keywords outside of blocks in `text`.
+/// some keywords, like `#+TODO:`, are needed before parsing headlines.
+fn scan_keywords(text: &str) -> Vec<(usize, Keyword<'_>)> {
+    let mut block: Option<&str> = None;
+    let mut keywords = Vec::new();
+    let mut lines = text.lines().enumerate();
+    while let Some((line_number, line)) = lines.next() {
+        match block {
+            Some(name) if Block::is_end(line, name) => block = None,
+            Some(_) => {}
+            None => {
+                let rest = lines.clone().map(|(_, line)| line);
+                match Block::parse_begin(line) {
+                    Some(begin) if is_terminated(rest, OpenBlock::Block(begin.name)) => {
+                        block = Some(begin.name)
+                    }
+                    _ => {
+                        keywords.extend(Keyword::parse(line).map(|keyword| (line_number, keyword)))
+                    }
+                }
+            }
+        }
+    }
+    keywords
 }
 
 trait Cursor<'t> {
     fn advance(&mut self) -> Option<Line<'t>>;
     fn current_line(&self) -> Option<&Line<'t>>;
     fn current_line_number(&self) -> Option<usize>;
+    /// look at the lines after the current line without advancing.
+    /// `f` is called on each line until it returns `Some`.
+    fn look_ahead<T, P: FnMut(&Line<'t>) -> Option<T>>(&self, f: P) -> Option<T>;
+    /// record a warning at the current line
+    fn warn(&mut self, message: String);
 }
 
 struct OrgCursor<'t, F> {
@@ -35,6 +153,10 @@ struct OrgCursor<'t, F> {
     current_line_number: Option<usize>,
     iterator: Enumerate<Lines<'t>>,
     transform: F,
+    /// the block the cursor is in.
+    /// lines in a block are not classified.
+    block: Option<OpenBlock<'t>>,
+    diagnostics: Vec<Diagnostic>,
 }
 
 impl<'t, F> OrgCursor<'t, F>
@@ -42,20 +164,87 @@ where
     F: Fn(RawLine<'t>) -> (usize, Line<'t>),
 {
     fn new(text: &'t str, transform: F) -> error::Result<OrgCursor<'t, F>> {
-        let mut iterator = text.lines().enumerate();
-        let current_line = iterator.next().map(&transform);
-        let (current_line_number, current_line) = current_line.ok_or(OrgError::ParseError(
-            Some(0),
-            "cannot parse empty text".to_string(),
-        ))?;
-        let (current_line_number, current_line) = (Some(current_line_number), Some(current_line));
-
-        Ok(OrgCursor {
-            current_line,
-            current_line_number,
-            iterator,
+        let mut cursor = OrgCursor {
+            current_line: None,
+            current_line_number: None,
+            iterator: text.lines().enumerate(),
             transform,
-        })
+            block: None,
+            diagnostics: Vec::new(),
+        };
+
+        cursor.advance();
+        if cursor.current_line.is_none() {
+            return Err(OrgError::ParseError(
+                Some(0),
+                "cannot parse empty text".to_string(),
+            ));
+        }
+
+        Ok(cursor)
+    }
+}
+
+/// a block that ends at a matching end line
+#[derive(Clone, Copy)]
+enum OpenBlock<'t> {
+    /// `#+BEGIN_NAME`, which ends at `#+END_NAME`
+    Block(&'t str),
+    /// `#+BEGIN: name`, which ends at `#+END:`
+    Dynamic,
+}
+
+impl<'t> OpenBlock<'t> {
+    fn is_end(self, line: &str) -> bool {
+        match self {
+            OpenBlock::Block(name) => Block::is_end(line, name),
+            OpenBlock::Dynamic => DynamicBlock::is_end(line),
+        }
+    }
+}
+
+/// check if a block is closed in `lines` before the next headline.
+/// blocks can't contain headlines, so they end with their section.
+fn is_terminated<'t, I: Iterator<Item = &'t str>>(lines: I, block: OpenBlock<'_>) -> bool {
+    lines
+        .take_while(|line| Headline::parse(line, &[]).is_none())
+        .any(|line| block.is_end(line))
+}
+
+/// classify the next line from `iterator`,
+/// keeping track of the block that the lines are in
+fn next_line<'t, F>(
+    iterator: &mut Enumerate<Lines<'t>>,
+    transform: &F,
+    block: &mut Option<OpenBlock<'t>>,
+) -> Option<(usize, Line<'t>)>
+where
+    F: Fn(RawLine<'t>) -> (usize, Line<'t>),
+{
+    let (line_num, raw) = iterator.next()?;
+
+    if let Some(open_block) = block {
+        if open_block.is_end(raw) {
+            *block = None;
+            return Some((line_num, Line::BlockEnd(raw)));
+        }
+        return Some((line_num, Line::Text(raw)));
+    }
+
+    let (line_num, line) = transform((line_num, raw));
+    let new_block = match &line {
+        Line::BlockBegin { block, .. } => Some(OpenBlock::Block(block.name)),
+        Line::DynamicBlockBegin { .. } => Some(OpenBlock::Dynamic),
+        _ => None,
+    };
+    match new_block {
+        Some(new_block) if is_terminated(iterator.clone().map(|(_, line)| line), new_block) => {
+            block.replace(new_block);
+            Some((line_num, line))
+        }
+        // a block without an end is just text
+        Some(_) => Some((line_num, Line::Text(raw))),
+        None => Some((line_num, line)),
     }
 }
 
@@ -68,7 +257,7 @@ where
     fn advance(&mut self) -> Option<Line<'t>> {
         let last_line = self.current_line.take();
 
-        match self.iterator.next().map(&self.transform) {
+        match next_line(&mut self.iterator, &self.transform, &mut self.block) {
             Some((line_num, line)) => {
                 self.current_line_number.replace(line_num);
                 self.current_line.replace(line);
@@ -89,13 +278,30 @@ where
     fn current_line_number(&self) -> Option<usize> {
         self.current_line_number
     }
+
+    fn look_ahead<T, P: FnMut(&Line<'t>) -> Option<T>>(&self, mut f: P) -> Option<T> {
+        let mut iterator = self.iterator.clone();
+        let mut block = self.block;
+        std::iter::from_fn(|| next_line(&mut iterator, &self.transform, &mut block))
+            .find_map(|(_, line)| f(&line))
+    }
+
+    fn warn(&mut self, message: String) {
+        self.diagnostics
+            .push(Diagnostic::new(self.current_line_number, message));
+    }
 }
 
 fn parse_headline_objects<'t, C: Cursor<'t>>(
     headline: Headline<'t>,
     cursor: &mut C,
-    possible_states: &[&str],
 ) -> error::Result<HeadlineGroup<'t>> {
+    let planning = if headline.level() > 0 {
+        parse_planning(cursor)
+    } else {
+        None
+    };
+    let properties = parse_property_drawer(cursor);
     let mut content: Option<LimitedVec<Content<'t>>> = None;
     let mut sub_headlines: Option<LimitedVec<HeadlineGroup<'t>>> = None;
 
@@ -115,7 +321,7 @@ fn parse_headline_objects<'t, C: Cursor<'t>>(
                         ))?;
 
                     // recurse and add subheader
-                    let sub_header = parse_headline_objects(new_header, cursor, possible_states)?;
+                    let sub_header = parse_headline_objects(new_header, cursor)?;
                     sub_headlines
                         .get_or_insert(Default::default())
                         .push(sub_header)?;
@@ -123,23 +329,227 @@ fn parse_headline_objects<'t, C: Cursor<'t>>(
                     break;
                 }
             }
-            Line::ListItem(_) => {
-                content
-                    .get_or_insert(Default::default())
-                    .push(parse_list(cursor).into())?;
-            }
-            Line::Text(_) => {
-                let text = parse_text(cursor);
-                content.get_or_insert(Default::default()).push(text)?;
+            _ => {
+                let object = parse_content(cursor, false)?;
+                content.get_or_insert(Default::default()).push(object)?;
             }
         }
     }
 
-    return Ok(HeadlineGroup {
+    Ok(HeadlineGroup {
         headline,
+        planning,
+        properties,
         content: content.map(LimitedVec::take),
         sub_headlines: sub_headlines.map(LimitedVec::take),
-    });
+        inherited_tags: Vec::new(),
+    })
+}
+
+/// parse the content starting at the current line.
+/// the cursor should not be at a headline.
+fn parse_content<'t, C: Cursor<'t>>(cursor: &mut C, in_drawer: bool) -> error::Result<Content<'t>> {
+    match cursor.current_line() {
+        Some(Line::ListItem(_)) => Ok(parse_list(cursor).into()),
+        Some(Line::DrawerBegin { name, .. }) if !in_drawer => {
+            if drawer_is_terminated(cursor) {
+                parse_drawer(cursor)
+            } else {
+                let message = format!("drawer `{}` is missing `{}`", name, DRAWER_END);
+                cursor.warn(message);
+                Ok(parse_text(cursor, in_drawer))
+            }
+        }
+        Some(Line::DrawerEnd(_)) if !in_drawer => {
+            cursor.warn(format!("`{}` found outside of a drawer", DRAWER_END));
+            Ok(parse_text(cursor, in_drawer))
+        }
+        Some(Line::BlockBegin { .. }) => Ok(parse_block(cursor)),
+        Some(Line::DynamicBlockBegin { .. }) => Ok(parse_dynamic_block(cursor)),
+        Some(Line::Clock { .. }) => Ok(parse_clock(cursor)),
+        Some(Line::TableRow { .. }) => Ok(parse_table(cursor)),
+        Some(Line::FootnoteDefinition { .. }) => parse_footnote_definition(cursor),
+        Some(Line::Keyword { .. }) => match cursor.advance() {
+            Some(Line::Keyword { keyword, .. }) => Ok(Content::Keyword(keyword)),
+            _ => unreachable!("the current line is a keyword"),
+        },
+        _ => Ok(parse_text(cursor, in_drawer)),
+    }
+}
+
+/// parse a block from `#+BEGIN_NAME` to `#+END_NAME`.
+/// the cursor does not classify the lines in the block.
+fn parse_block<'t, C: Cursor<'t>>(cursor: &mut C) -> Content<'t> {
+    let mut block = match cursor.advance() {
+        Some(Line::BlockBegin { block, .. }) => block,
+        _ => unreachable!("parse_block called without a block"),
+    };
+
+    while let Some(line) = cursor.advance() {
+        match line {
+            Line::BlockEnd(_) => break,
+            line => block.body.extend(line.text()),
+        }
+    }
+
+    Content::Block(block)
+}
+
+/// parse a dynamic block from `#+BEGIN: name` to `#+END:`.
+/// the body is kept verbatim so that it can be regenerated.
+fn parse_dynamic_block<'t, C: Cursor<'t>>(cursor: &mut C) -> Content<'t> {
+    let mut block = match cursor.advance() {
+        Some(Line::DynamicBlockBegin { block, .. }) => block,
+        _ => unreachable!("parse_dynamic_block called without a dynamic block"),
+    };
+
+    while let Some(line) = cursor.advance() {
+        match line {
+            Line::BlockEnd(end) => {
+                block.end = Some(end);
+                break;
+            }
+            line => block.body.extend(line.text().map(Cow::Borrowed)),
+        }
+    }
+
+    Content::DynamicBlock(block)
+}
+
+/// parse a clock line and check its duration against its timestamps
+fn parse_clock<'t, C: Cursor<'t>>(cursor: &mut C) -> Content<'t> {
+    let clock = match cursor.current_line() {
+        Some(Line::Clock { clock, .. }) => *clock,
+        _ => unreachable!("parse_clock called without a clock"),
+    };
+
+    match (clock.duration, clock.minutes()) {
+        (_, Some(minutes)) if minutes < 0 => cursor.warn("clock ends before it starts".to_string()),
+        (Some(duration), Some(minutes)) if duration != minutes => cursor.warn(format!(
+            "clock duration `{}` does not match its timestamps, `{}`",
+            format_duration(duration),
+            format_duration(minutes)
+        )),
+        _ => {}
+    }
+
+    cursor.advance();
+    Content::Clock(clock)
+}
+
+/// parse the rows of a table and the `#+TBLFM:` lines after it
+fn parse_table<'t, C: Cursor<'t>>(cursor: &mut C) -> Content<'t> {
+    let mut table = Table::new();
+    while let Some(Line::TableRow { .. }) = cursor.current_line() {
+        if let Some(Line::TableRow { row, raw }) = cursor.advance() {
+            table.push_line(row, raw);
+        }
+    }
+
+    while let Some(Line::Keyword { keyword, .. }) = cursor.current_line() {
+        if !keyword.is(TBLFM) {
+            break;
+        }
+        if table.formulas.is_empty() {
+            table.formulas_line = cursor.current_line_number();
+        }
+        table.formulas.push(*keyword);
+        cursor.advance();
+    }
+
+    Content::Table(table)
+}
+
+/// parse a footnote definition and the content after its first line.
+/// the definition ends at the next definition, the next headline
+/// or two blank lines, which are left for the parent.
+fn parse_footnote_definition<'t, C: Cursor<'t>>(cursor: &mut C) -> error::Result<Content<'t>> {
+    let mut definition = match cursor.advance() {
+        Some(Line::FootnoteDefinition { definition, .. }) => definition,
+        _ => unreachable!("parse_footnote_definition called without a definition"),
+    };
+
+    let mut contents: LimitedVec<Content<'t>> = Default::default();
+    let mut text_lines: Vec<&'t str> = Vec::new();
+    loop {
+        match cursor.current_line() {
+            None | Some(Line::Header(_)) | Some(Line::FootnoteDefinition { .. }) => break,
+            Some(line @ Line::Text(_)) => {
+                let ends_definition = line.is_blank()
+                    && cursor
+                        .look_ahead(|next| Some(next.is_blank()))
+                        .unwrap_or(true);
+                if ends_definition {
+                    break;
+                }
+                if let Some(line) = cursor.advance().as_ref().and_then(Line::text) {
+                    text_lines.push(line);
+                }
+            }
+            Some(_) => {
+                if !text_lines.is_empty() {
+                    contents.push(Content::Text(text_lines))?;
+                    text_lines = Vec::new();
+                }
+                contents.push(parse_content(cursor, false)?)?;
+            }
+        }
+    }
+    if !text_lines.is_empty() {
+        contents.push(Content::Text(text_lines))?;
+    }
+
+    definition.contents = contents.take();
+    Ok(Content::Footnote(definition))
+}
+
+/// check that the drawer at the current line has an `:END:`
+/// before the next headline
+fn drawer_is_terminated<'t, C: Cursor<'t>>(cursor: &C) -> bool {
+    cursor
+        .look_ahead(|line| match line {
+            Line::DrawerEnd(_) => Some(true),
+            Line::Header(_) => Some(false),
+            _ => None,
+        })
+        .unwrap_or(false)
+}
+
+/// parse a terminated drawer from `:NAME:` to `:END:`.
+/// drawers cannot be nested, so drawer lines inside of the drawer are text.
+fn parse_drawer<'t, C: Cursor<'t>>(cursor: &mut C) -> error::Result<Content<'t>> {
+    let (name, begin) = match cursor.advance() {
+        Some(Line::DrawerBegin { name, raw }) => (name, raw),
+        _ => {
+            return Err(OrgError::ParseError(
+                cursor.current_line_number(),
+                "cursor returned a bad value".to_string(),
+            ))
+        }
+    };
+
+    let mut contents: LimitedVec<Content<'t>> = Default::default();
+    let end = loop {
+        match cursor.current_line() {
+            Some(Line::DrawerEnd(end)) => {
+                let end = *end;
+                cursor.advance();
+                break end;
+            }
+            Some(Line::Header(_)) | None => {
+                cursor.warn(format!("drawer `{}` is missing `{}`", name, DRAWER_END));
+                break DRAWER_END;
+            }
+            _ => contents.push(parse_content(cursor, true)?)?,
+        }
+    };
+
+    Ok(Content::Drawer {
+        name,
+        contents: contents.take(),
+        begin,
+        end,
+    })
 }
 
 struct LimitedVec<T> {
@@ -183,41 +593,167 @@ impl<T> Limited<T> for LimitedVec<T> {
     }
 }
 
+/// parse the planning line if the cursor is at one
+fn parse_planning<'t, C: Cursor<'t>>(cursor: &mut C) -> Option<Planning> {
+    match cursor.current_line() {
+        Some(Line::Planning { .. }) => match cursor.advance() {
+            Some(Line::Planning { planning, .. }) => Some(planning),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// parse a `:PROPERTIES:` drawer if the cursor is at the start of one.
+/// property drawers with lines that are not properties are left
+/// to be parsed as regular drawers.
+fn parse_property_drawer<'t, C: Cursor<'t>>(cursor: &mut C) -> Option<Properties<'t>> {
+    match cursor.current_line() {
+        Some(Line::DrawerBegin { name, .. }) if name.eq_ignore_ascii_case(PROPERTIES) => {}
+        _ => return None,
+    }
+
+    let is_valid = cursor
+        .look_ahead(|line| match line {
+            Line::DrawerEnd(_) => Some(true),
+            line => match line.text().and_then(Property::parse) {
+                Some(_) => None,
+                None => Some(false),
+            },
+        })
+        .unwrap_or(false);
+
+    if !is_valid {
+        // a missing `:END:` is reported when the drawer is parsed as text
+        if drawer_is_terminated(cursor) {
+            cursor.warn("malformed property drawer".to_string());
+        }
+        return None;
+    }
+
+    let begin = cursor.advance().as_ref().and_then(Line::text);
+    let mut properties = Properties::default();
+    while let Some(line) = cursor.advance() {
+        match line.text().and_then(Property::parse) {
+            Some(property) => properties.push(property),
+            None => {
+                if let (Some(begin), Some(end)) = (begin, line.text()) {
+                    properties.set_lines(begin, end);
+                }
+                break;
+            }
+        }
+    }
+    Some(properties)
+}
+
+/// parse a list starting at the current line.
+/// the list continues while there are items with the same
+/// indentation and a matching bullet.
 fn parse_list<'t, C: Cursor<'t>>(cursor: &mut C) -> Vec<ListItem<'t>> {
     let mut list_items = Vec::new();
-    let mut bullet_opt: Option<Bullet> = None;
+    let mut first: Option<(usize, Bullet)> = None;
     while let Some(Line::ListItem(item)) = cursor.current_line() {
-        let bullet = bullet_opt.get_or_insert(item.bullet);
-        if !item.bullet.matches(bullet) {
+        let (indent, bullet) = *first.get_or_insert((item.indent, item.bullet));
+        if item.indent != indent || !item.bullet.matches(&bullet) {
             break;
         }
-        match cursor.advance() {
-            Some(Line::ListItem(item)) => list_items.push(item),
-            _ => eprintln!("unexpected error"),
+        if let Some(Line::ListItem(item)) = cursor.advance() {
+            list_items.push(parse_list_item_contents(cursor, item));
+        }
+    }
+    list_items
+}
+
+/// parse the lines that belong to `item`:
+/// lines indented more than the bullet, including nested lists.
+/// a single blank line is part of the item if the list continues after it.
+fn parse_list_item_contents<'t, C: Cursor<'t>>(
+    cursor: &mut C,
+    mut item: ListItem<'t>,
+) -> ListItem<'t> {
+    let mut text_lines: Vec<&'t str> = Vec::new();
+
+    while let Some(line) = cursor.current_line() {
+        let belongs_to_item = if line.is_blank() {
+            cursor
+                .look_ahead(|next| Some(list_continues_after_blank(next, &item)))
+                .unwrap_or(false)
+        } else {
+            line.text().is_some() && line.indentation() > item.indent
+        };
+
+        match line {
+            Line::ListItem(child) if child.indent > item.indent => {
+                if !text_lines.is_empty() {
+                    item.contents.push(Content::Text(text_lines));
+                    text_lines = Vec::new();
+                }
+                let child_list = parse_list(cursor);
+                item.contents.push(child_list.into());
+            }
+            _ if belongs_to_item => {
+                if let Some(line) = cursor.advance().as_ref().and_then(Line::text) {
+                    text_lines.push(line);
+                }
+            }
+            _ => break,
         }
     }
-    return list_items;
+
+    if !text_lines.is_empty() {
+        item.contents.push(Content::Text(text_lines));
+    }
+    item
 }
 
-fn parse_text<'t, C: Cursor<'t>>(cursor: &mut C) -> Content<'t> {
+/// check if the line after a blank line continues `item` or its list
+fn list_continues_after_blank(next: &Line<'_>, item: &ListItem<'_>) -> bool {
+    match next {
+        Line::ListItem(next_item) => next_item.indent >= item.indent,
+        next => !next.is_blank() && next.text().is_some() && next.indentation() > item.indent,
+    }
+}
+
+/// parse text lines starting at the current line.
+/// inside of a drawer, drawer lines are also text.
+fn parse_text<'t, C: Cursor<'t>>(cursor: &mut C, in_drawer: bool) -> Content<'t> {
     let mut text_lines = Vec::new();
-    while let Some(Line::Text(_)) = cursor.current_line() {
-        match cursor.advance() {
-            Some(Line::Text(line)) => text_lines.push(line),
-            _ => eprintln!("unexpected error"),
+    if let Some(line) = cursor.advance().as_ref().and_then(Line::text) {
+        text_lines.push(line);
+    }
+    while let Some(line) = cursor.current_line() {
+        match line {
+            Line::Text(_) | Line::DrawerBegin { .. } if in_drawer => {}
+            Line::Text(_) | Line::Planning { .. } => {}
+            _ => break,
+        }
+        if let Some(line) = cursor.advance().as_ref().and_then(Line::text) {
+            text_lines.push(line);
         }
     }
     Content::Text(text_lines)
 }
 
-fn raw_line_to_line<'t>(raw_line: RawLine<'t>, possible_states: &[&str]) -> (usize, Line<'t>) {
+fn raw_line_to_line<'t>(
+    raw_line: RawLine<'t>,
+    possible_states: &[&str],
+    alphabetical_lists: bool,
+) -> (usize, Line<'t>) {
     let (line_num, line) = raw_line;
-    (line_num, line::parse_line(line, possible_states))
+    (
+        line_num,
+        line::parse_line_with(line, possible_states, alphabetical_lists),
+    )
 }
 
 #[cfg(test)]
+#[allow(clippy::needless_borrow)]
 mod tests {
     use super::*;
+    use crate::block::BlockKind;
+    use crate::content::ListKind;
+    use crate::timestamp::Timestamp;
 
     const TEST_STATES: [&str; 3] = ["TODO", "STARTED", "DONE"];
 
@@ -285,7 +821,8 @@ mod tests {
 
         assert_eq!(headlines.len(), expected_headline_num);
 
-        let expected_objects: usize = 9;
+        // the `:DEADLINE:` drawer and its text are objects
+        let expected_objects: usize = 11;
 
         assert_eq!(object_num, expected_objects);
 
@@ -297,6 +834,133 @@ mod tests {
         assert_eq!(content_lines, test_text_lines);
     }
 
+    const PROPERTY_TEXT: &str = "* a headline with properties
+:PROPERTIES:
+:ID: 1234-abcd
+:EFFORT:   1:30
+:VAR: a=1
+:VAR+: b=2
+:END:
+some text
+** a sub headline
+  :properties:
+  :CUSTOM_ID: sub
+  :end:
+";
+
+    #[test]
+    fn parse_property_drawers() {
+        let document = parse_org_text(PROPERTY_TEXT, TEST_STATES.to_vec()).unwrap();
+        let headlines: Vec<_> = document.headlines().collect();
+
+        assert_eq!(headlines.len(), 2);
+        assert_eq!(headlines[0].property("id").as_deref(), Some("1234-abcd"));
+        assert_eq!(headlines[0].property("EFFORT").as_deref(), Some("1:30"));
+        assert_eq!(headlines[0].property("VAR").as_deref(), Some("a=1 b=2"));
+        assert_eq!(headlines[0].content_len(), 1);
+        assert_eq!(headlines[1].property("CUSTOM_ID").as_deref(), Some("sub"));
+
+        assert_eq!(format!("{}", document), PROPERTY_TEXT);
+    }
+
+    #[test]
+    fn unterminated_property_drawer() {
+        let text = "* headline\n:PROPERTIES:\n:ID: 1\n* next headline\n";
+        let document = parse_org_text(text, TEST_STATES.to_vec()).unwrap();
+
+        assert_eq!(document.headlines().count(), 2);
+        assert!(document
+            .root
+            .sub_headlines()
+            .all(|h| h.properties().is_none()));
+        assert_eq!(document.diagnostics.len(), 1);
+        assert_eq!(document.diagnostics[0].line, Some(1));
+        assert_eq!(
+            document.diagnostics[0].message,
+            "drawer `PROPERTIES` is missing `:END:`"
+        );
+    }
+
+    const PLANNING_TEXT: &str = "* TODO a scheduled task
+DEADLINE: <2020-03-01 Sun> SCHEDULED: <2020-02-28 Fri>
+:PROPERTIES:
+:ID: task
+:END:
+* DONE a closed task
+CLOSED: [2020-02-27 Thu 10:00]
+* a headline with text
+SCHEDULED: <2020-02-28 Fri>
+is not a planning line when it is not right after the headline
+SCHEDULED: <2020-02-28 Fri>
+";
+
+    #[test]
+    fn parse_planning_lines() {
+        let document = parse_org_text(PLANNING_TEXT, TEST_STATES.to_vec()).unwrap();
+        let headlines: Vec<_> = document.headlines().collect();
+
+        let planning = headlines[0].planning().unwrap();
+        assert_eq!(planning.deadline, Timestamp::parse("<2020-03-01 Sun>"));
+        assert_eq!(planning.scheduled, Timestamp::parse("<2020-02-28 Fri>"));
+        assert_eq!(planning.closed, None);
+        assert_eq!(headlines[0].property("ID").as_deref(), Some("task"));
+
+        let planning = headlines[1].planning().unwrap();
+        assert_eq!(planning.closed, Timestamp::parse("[2020-02-27 Thu 10:00]"));
+
+        assert!(headlines[2].planning().is_some());
+        assert_eq!(headlines[2].content_len(), 1);
+
+        assert_eq!(format!("{}", document), PLANNING_TEXT);
+    }
+
+    const DRAWER_TEXT: &str = "* a headline with drawers
+  :logbook:
+  - State \"DONE\"       from \"TODO\"
+  - State \"TODO\"       from
+  a note in the logbook
+  :END:
+text between drawers
+:CUSTOM:
+:NESTED:
+:END:
+";
+
+    #[test]
+    fn parse_drawers() {
+        let document = parse_org_text(DRAWER_TEXT, TEST_STATES.to_vec()).unwrap();
+        let headline = document.headlines().next().unwrap();
+        let content: Vec<_> = headline.content().collect();
+
+        assert_eq!(content.len(), 3);
+        match content[0] {
+            Content::Drawer { name, contents, .. } => {
+                assert_eq!(*name, "logbook");
+                assert_eq!(contents.len(), 2);
+                assert!(matches!(contents[0], Content::List(ref items) if items.len() == 2));
+                assert!(matches!(contents[1], Content::Text(_)));
+            }
+            _ => panic!("expected a drawer, found: {:?}", content[0]),
+        }
+        assert!(matches!(content[2], Content::Drawer { name: "CUSTOM", .. }));
+
+        // headline, 2 drawers with 3 children and the text
+        assert_eq!(document.objects().count(), 7);
+        assert!(document.diagnostics.is_empty());
+        assert_eq!(format!("{}", document), DRAWER_TEXT);
+    }
+
+    #[test]
+    fn unterminated_drawer() {
+        let text = "* headline\n:LOGBOOK:\n- item\n* next headline\nsome text\n";
+        let document = parse_org_text(text, TEST_STATES.to_vec()).unwrap();
+
+        assert_eq!(document.headlines().count(), 2);
+        assert_eq!(document.diagnostics.len(), 1);
+        assert_eq!(document.diagnostics[0].line, Some(1));
+        assert_eq!(format!("{}", document), text);
+    }
+
     fn check_list<'t>(
         list: &Vec<ListItem<'t>>,
         expected_len: usize,
@@ -315,12 +979,12 @@ mod tests {
 
     fn check_bullets<'t>(list: &Vec<ListItem<'t>>, expected_bullet: Bullet) -> error::Result<()> {
         match expected_bullet {
-            Bullet::Numeric(_) => {
+            Bullet::Numeric(..) => {
                 match list
                     .iter()
                     .enumerate()
                     .find(|(num, item)| match item.bullet {
-                        Bullet::Numeric(i) => *num != (i + 1),
+                        Bullet::Numeric(i, _) => *num != (i + 1),
                         _ => false,
                     }) {
                     Some((bad_bullet_index, bad_bullet)) => Err(OrgError::ParseError(
@@ -348,7 +1012,262 @@ mod tests {
     }
 
     fn get_cursor<'t>(s: &'t str) -> error::Result<impl Cursor<'t>> {
-        OrgCursor::new(s, |raw_line| raw_line_to_line(raw_line, &TEST_STATES))
+        OrgCursor::new(s, |raw_line| {
+            raw_line_to_line(raw_line, &TEST_STATES, false)
+        })
+    }
+
+    const NESTED_LIST: &str = "- first item [0/0]
+  with a second line
+  - [X] nested item
+    with its own body
+  - [ ] second nested item
+
+- second item after a blank line
+  1. ordered child
+text after the list";
+
+    #[test]
+    fn parse_nested_lists() {
+        let mut cursor = get_cursor(NESTED_LIST).unwrap();
+        let mut list = parse_list(&mut cursor);
+
+        assert_eq!(list.len(), 2);
+        assert!(matches!(
+            cursor.current_line(),
+            Some(Line::Text("text after the list"))
+        ));
+
+        let children: Vec<_> = list[0].children().collect();
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[0].indent, 2);
+        assert!(matches!(children[0].contents[0], Content::Text(ref lines) if lines.len() == 1));
+        assert!(matches!(list[0].contents[0], Content::Text(ref lines) if lines.len() == 1));
+        // the blank line belongs to the first item
+        assert!(matches!(list[0].contents[2], Content::Text(ref lines) if lines == &[""]));
+        assert_eq!(list[1].children().count(), 1);
+
+        list[0].update_statistics();
+        assert_eq!(
+            format!("{}", Content::List(list)),
+            NESTED_LIST
+                .replace("[0/0]", "[1/2]")
+                .trim_end_matches("text after the list")
+        );
+    }
+
+    #[test]
+    fn keep_list_indentation() {
+        let text = "* tabs\n\t- item\n\t\t- nested\n\t  continued\n";
+        let document = parse_org_text(text, TEST_STATES.to_vec()).unwrap();
+        let headline = document.headlines().next().unwrap();
+        match headline.content().next() {
+            Some(Content::List(items)) => assert_eq!(items[0].children().count(), 1),
+            content => panic!("expected a list, found: {:?}", content),
+        }
+        assert_eq!(format!("{}", document), text);
+    }
+
+    #[test]
+    fn keep_list_items_as_written() {
+        let text = "* items\n-  [x]  spaced [/]\n  - [x] done\n  - [ ] todo\n01. zero\n";
+        let mut document = parse_org_text(text, TEST_STATES.to_vec()).unwrap();
+        assert_eq!(format!("{}", document), text);
+
+        // only the changed item is printed again
+        document.update_statistics();
+        assert_eq!(
+            format!("{}", document),
+            text.replace("-  [x]  spaced [/]", "- [X] spaced [1/2]")
+        );
+    }
+
+    #[test]
+    fn parse_alphabetical_lists() {
+        let text = "A. Smith said so\na. first\nb. second\n";
+        let document = parse_org_text(text, TEST_STATES.to_vec()).unwrap();
+        assert!(document
+            .root
+            .content()
+            .all(|content| content.list_kind().is_none()));
+
+        let config = crate::config::Builder::default()
+            .file_path("notes.org".to_string())
+            .allow_alphabetical_lists(true)
+            .build()
+            .unwrap();
+        let document = parse_org_text_with_config(text, &config).unwrap();
+        let lists: Vec<_> = document
+            .root
+            .content()
+            .filter_map(Content::list_kind)
+            .collect();
+        assert_eq!(lists, vec![ListKind::Ordered]);
+        assert_eq!(format!("{}", document), text);
+    }
+
+    #[test]
+    fn lists_end_at_two_blank_lines() {
+        let text = "- item\n\n\n  indented text\n- new list\n";
+        let document = parse_org_text(text, TEST_STATES.to_vec()).unwrap();
+        let content: Vec<_> = document.root.content().collect();
+
+        assert_eq!(content.len(), 3);
+        assert!(matches!(content[0], Content::List(ref items) if items.len() == 1));
+        assert_eq!(format!("{}", document), text);
+    }
+
+    const TABLE_TEXT: &str = "* inventory
+| <l>   |  <r5> |
+| item  | count |
+|-------+-------|
+| nuts  |     3 |
+| bolts |    12 |
+#+TBLFM: @>$2=vsum(@2..@-1)
+#+tblfm: $1=hello
+some text
+| a | b
+";
+
+    #[test]
+    fn parse_tables() {
+        let document = parse_org_text(TABLE_TEXT, TEST_STATES.to_vec()).unwrap();
+        let headline = document.headlines().next().unwrap();
+        let content: Vec<_> = headline.content().collect();
+        assert_eq!(content.len(), 3);
+
+        let table = match content[0] {
+            Content::Table(table) => table,
+            content => panic!("expected a table, found {:?}", content),
+        };
+        assert_eq!(table.rows.len(), 5);
+        assert!(table.rows[2].is_rule());
+        let formulas: Vec<_> = table.formulas.iter().map(|keyword| keyword.value).collect();
+        assert_eq!(formulas, vec!["@>$2=vsum(@2..@-1)", "$1=hello"]);
+        assert_eq!(table.column_count(), 2);
+        assert_eq!(table.cell(0, 1), Some("count"));
+        assert_eq!(table.cell(2, 0), Some("bolts"));
+        assert_eq!(table.cell(3, 0), None);
+        assert_eq!(
+            table.columns().collect::<Vec<_>>(),
+            vec![vec!["item", "nuts", "bolts"], vec!["count", "3", "12"]]
+        );
+        assert_eq!(table.column_cookie(1).unwrap().width, Some(5));
+
+        assert!(matches!(content[1], Content::Text(_)));
+        assert!(matches!(content[2], Content::Table(table) if table.cell(0, 1) == Some("b")));
+
+        assert_eq!(
+            format!("{}", document),
+            TABLE_TEXT.replace("| a | b\n", "| a | b |\n")
+        );
+    }
+
+    const BLOCK_TEXT: &str = "* a headline with code
+#+BEGIN_SRC rust :results output
+,* not a headline
+- not a list item
+:NOT_A_DRAWER:
+#+END_SRC
+#+begin_quote
+quoted text
+#+end_quote
+#+BEGIN_EXAMPLE
+an unterminated block is text
+* a real headline
+";
+
+    #[test]
+    fn parse_blocks() {
+        let document = parse_org_text(BLOCK_TEXT, TEST_STATES.to_vec()).unwrap();
+        let headlines: Vec<_> = document.headlines().collect();
+        assert_eq!(headlines.len(), 2);
+
+        let content: Vec<_> = headlines[0].content().collect();
+        assert_eq!(content.len(), 3);
+        match content[0] {
+            Content::Block(block) => {
+                assert_eq!(block.kind, BlockKind::Src);
+                assert_eq!(block.language, Some("rust"));
+                assert_eq!(block.header_arg(":results"), Some("output"));
+                assert_eq!(
+                    block.body,
+                    vec![",* not a headline", "- not a list item", ":NOT_A_DRAWER:"]
+                );
+            }
+            _ => panic!("expected a block, found: {:?}", content[0]),
+        }
+        assert!(matches!(content[1], Content::Block(ref block) if block.kind == BlockKind::Quote));
+        assert!(matches!(content[2], Content::Text(ref lines) if lines.len() == 2));
+
+        assert_eq!(format!("{}", document), BLOCK_TEXT);
+    }
+
+    #[test]
+    fn blocks_end_with_their_section() {
+        let text = "* a\n#+BEGIN_SRC rust\nlet a = 1;\n* b\ntext\n* c\n#+END_SRC\n";
+        let document = parse_org_text(text, TEST_STATES.to_vec()).unwrap();
+        let titles: Vec<_> = document
+            .root
+            .sub_headlines()
+            .map(|headline| headline.headline.title())
+            .collect();
+        assert_eq!(titles, vec!["a", "b", "c"]);
+        assert!(document
+            .headlines()
+            .flat_map(|headline| headline.content())
+            .all(|content| matches!(content, Content::Text(_))));
+        assert_eq!(format!("{}", document), text);
+
+        let text = "#+BEGIN_SRC\n* a\n#+TODO: NEXT | DONE\n* NEXT b\n#+END_SRC\n";
+        let document = parse_org_text(text, TEST_STATES.to_vec()).unwrap();
+        let b = document.root.sub_headlines().nth(1).unwrap();
+        assert_eq!(b.headline.status(), Some("NEXT"));
+
+        let text = "* a\n#+BEGIN: tasks\n* b\n  #+begin: tasks :done t\n- a task\n  #+end:\n";
+        let document = parse_org_text(text, TEST_STATES.to_vec()).unwrap();
+        let content: Vec<_> = document
+            .headlines()
+            .map(|headline| headline.content().collect::<Vec<_>>())
+            .collect();
+        assert!(matches!(content[0][..], [Content::Text(_)]));
+        assert!(matches!(
+            content[1][..],
+            [Content::DynamicBlock(ref block)] if block.body == vec!["- a task"]
+        ));
+        assert_eq!(format!("{}", document), text);
+    }
+
+    const TODO_SEQUENCE_TEXT: &str = "#+TODO: TODO(t) NEXT(n) | DONE(d) CANCELLED(c)
+#+TYP_TODO: BUG FEATURE | FIXED
+* NEXT a next action
+* CANCELLED a cancelled task
+* FEATURE a feature request
+* STARTED is not a status in this file
+* NEXTSTEP is not a status
+";
+
+    #[test]
+    fn parse_in_file_todo_sequences() {
+        let document = parse_org_text(TODO_SEQUENCE_TEXT, TEST_STATES.to_vec()).unwrap();
+        let statuses: Vec<_> = document
+            .headlines()
+            .map(|headline| headline.headline.status())
+            .collect();
+
+        assert_eq!(document.status_labels.sequences().len(), 2);
+        assert!(document.status_labels.is_done("CANCELLED"));
+        assert!(document.status_labels.is_done("FIXED"));
+        let done: Vec<_> = document
+            .headlines()
+            .map(|headline| headline.headline.is_done(&document.status_labels))
+            .collect();
+        assert_eq!(done, vec![false, true, false, false, false]);
+        assert_eq!(
+            statuses,
+            vec![Some("NEXT"), Some("CANCELLED"), Some("FEATURE"), None, None]
+        );
+        assert_eq!(format!("{}", document), TODO_SEQUENCE_TEXT);
     }
 
     #[test]
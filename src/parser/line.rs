@@ -1,6 +1,7 @@
 use crate::{
-    content::{Bullet, ListItem},
-    headline::Headline,
+    block::Block, clock::Clock, content::ListItem, dynamic_block::DynamicBlock,
+    footnote::FootnoteDefinition, headline::Headline, keyword::Keyword, planning::Planning,
+    table::Row,
 };
 
 #[derive(Debug)]
@@ -8,29 +9,158 @@ pub enum Line<'t> {
     Text(&'t str),
     Header(Headline<'t>),
     ListItem(ListItem<'t>),
+    DrawerBegin {
+        name: &'t str,
+        raw: &'t str,
+    },
+    DrawerEnd(&'t str),
+    Planning {
+        planning: Planning,
+        raw: &'t str,
+    },
+    Clock {
+        clock: Clock<'t>,
+        raw: &'t str,
+    },
+    BlockBegin {
+        block: Block<'t>,
+        raw: &'t str,
+    },
+    DynamicBlockBegin {
+        block: DynamicBlock<'t>,
+        raw: &'t str,
+    },
+    BlockEnd(&'t str),
+    Keyword {
+        keyword: Keyword<'t>,
+        raw: &'t str,
+    },
+    TableRow {
+        row: Row<'t>,
+        raw: &'t str,
+    },
+    FootnoteDefinition {
+        definition: FootnoteDefinition<'t>,
+        raw: &'t str,
+    },
+}
+
+impl<'t> Line<'t> {
+    /// the indentation of the line.
+    /// headlines are never indented.
+    pub fn indentation(&self) -> usize {
+        match self {
+            Line::Header(_) => 0,
+            Line::ListItem(item) => item.indent,
+            line => line.text().map(indentation).unwrap_or(0),
+        }
+    }
+
+    pub fn is_blank(&self) -> bool {
+        match self {
+            Line::Text(raw) => raw.trim().is_empty(),
+            _ => false,
+        }
+    }
+
+    /// the raw text of lines that can be read as plain text
+    /// when they are out of place
+    pub fn text(&self) -> Option<&'t str> {
+        match self {
+            Line::Text(raw)
+            | Line::DrawerBegin { raw, .. }
+            | Line::DrawerEnd(raw)
+            | Line::Planning { raw, .. }
+            | Line::Clock { raw, .. }
+            | Line::BlockBegin { raw, .. }
+            | Line::DynamicBlockBegin { raw, .. }
+            | Line::BlockEnd(raw)
+            | Line::Keyword { raw, .. }
+            | Line::TableRow { raw, .. }
+            | Line::FootnoteDefinition { raw, .. } => Some(raw),
+            _ => None,
+        }
+    }
 }
 
 // TODO only parse_line should be pub
 
 pub fn parse_line<'t>(line: &'t str, possible_states: &[&str]) -> Line<'t> {
     Headline::parse(line, possible_states)
-        .map(|header| Line::Header(header))
-        .or(parse_list_item(line))
+        .map(Line::Header)
+        .or_else(|| {
+            Planning::parse(line).map(|planning| Line::Planning {
+                planning,
+                raw: line,
+            })
+        })
+        .or_else(|| Clock::parse(line).map(|clock| Line::Clock { clock, raw: line }))
+        .or_else(|| Block::parse_begin(line).map(|block| Line::BlockBegin { block, raw: line }))
+        .or_else(|| {
+            DynamicBlock::parse_begin(line)
+                .map(|block| Line::DynamicBlockBegin { block, raw: line })
+        })
+        .or_else(|| Keyword::parse(line).map(|keyword| Line::Keyword { keyword, raw: line }))
+        .or_else(|| Row::parse(line).map(|row| Line::TableRow { row, raw: line }))
+        .or_else(|| {
+            FootnoteDefinition::parse(line).map(|definition| Line::FootnoteDefinition {
+                definition,
+                raw: line,
+            })
+        })
+        .or_else(|| parse_drawer_line(line))
+        .or_else(|| parse_list_item(line, false))
         .unwrap_or(Line::Text(line))
 }
 
-pub fn parse_list_item<'t>(line: &'t str) -> Option<Line<'t>> {
-    line.trim()
-        .find(' ')
-        .map(|space_index| line.split_at(space_index))
-        .and_then(|(bullet_str, rem)| Bullet::parse(bullet_str).map(|bullet| (bullet, rem.trim())))
-        .map(|(bullet, content)| ListItem { bullet, content })
-        .map(|list_item| Line::ListItem(list_item))
+/// parse a line, reading `a.` and `a)` as bullets
+/// if `alphabetical_lists` is set
+pub fn parse_line_with<'t>(
+    line: &'t str,
+    possible_states: &[&str],
+    alphabetical_lists: bool,
+) -> Line<'t> {
+    match parse_line(line, possible_states) {
+        Line::Text(_) if alphabetical_lists => {
+            parse_list_item(line, true).unwrap_or(Line::Text(line))
+        }
+        parsed => parsed,
+    }
+}
+
+/// parse `:NAME:` and `:END:` lines
+pub fn parse_drawer_line(line: &str) -> Option<Line<'_>> {
+    let name = line
+        .trim()
+        .strip_prefix(':')
+        .and_then(|rem| rem.strip_suffix(':'))
+        .filter(|name| !name.is_empty())
+        .filter(|name| {
+            name.chars()
+                .all(|ch| ch.is_alphanumeric() || ch == '-' || ch == '_')
+        })?;
+
+    if name.eq_ignore_ascii_case("END") {
+        Some(Line::DrawerEnd(line))
+    } else {
+        Some(Line::DrawerBegin { name, raw: line })
+    }
+}
+
+pub fn parse_list_item(line: &str, alphabetical_lists: bool) -> Option<Line<'_>> {
+    ListItem::parse(line, alphabetical_lists).map(Line::ListItem)
+}
+
+/// the number of whitespace characters at the start of `line`
+pub fn indentation(line: &str) -> usize {
+    line.chars().take_while(|ch| ch.is_whitespace()).count()
 }
 
 #[cfg(test)]
+#[allow(clippy::assertions_on_constants, clippy::match_like_matches_macro)]
 mod tests {
     use super::*;
+    use crate::content::{Bullet, Checkbox, Delimiter, ListKind};
 
     const TEST_STATES: [&str; 3] = ["TODO", "STARTED", "DONE"];
 
@@ -46,11 +176,25 @@ mod tests {
         "* STARTED first level started :with:tags",
     ];
 
-    const GOOD_LIST_ITEMS: [&str; 4] = [
+    const GOOD_LIST_ITEMS: [&str; 7] = [
         "- list item",
         "+ plus list item",
         "1. numbered list item",
         "6. sixth list item",
+        "2) parenthesis list item",
+        "  * indented star list item",
+        "3. [@3] counter list item",
+    ];
+
+    const ALPHABETICAL_LIST_ITEMS: [&str; 2] =
+        ["a. alphabetical list item", "B) upper case list item"];
+
+    const NOT_LIST_ITEMS: [&str; 5] = [
+        "2020 was a year",
+        "ab. is not a bullet",
+        "1.5 is a number",
+        "-not a list item",
+        "A. Smith said so",
     ];
 
     #[test]
@@ -69,6 +213,153 @@ mod tests {
         }))
     }
 
+    #[test]
+    fn test_drawer_lines() {
+        assert!(matches!(
+            parse_line(":LOGBOOK:", &TEST_STATES),
+            Line::DrawerBegin {
+                name: "LOGBOOK",
+                ..
+            }
+        ));
+        assert!(matches!(
+            parse_line("  :my-drawer: ", &TEST_STATES),
+            Line::DrawerBegin {
+                name: "my-drawer",
+                ..
+            }
+        ));
+        assert!(matches!(
+            parse_line(":end:", &TEST_STATES),
+            Line::DrawerEnd(_)
+        ));
+        assert!(matches!(
+            parse_line(":ID: value", &TEST_STATES),
+            Line::Text(_)
+        ));
+        assert!(matches!(parse_line("::", &TEST_STATES), Line::Text(_)));
+    }
+
+    #[test]
+    fn test_planning_lines() {
+        assert!(matches!(
+            parse_line("SCHEDULED: <2020-02-28 Fri>", &TEST_STATES),
+            Line::Planning { .. }
+        ));
+        assert!(matches!(
+            parse_line("SCHEDULED: is not a timestamp", &TEST_STATES),
+            Line::Text(_)
+        ));
+    }
+
+    #[test]
+    fn test_checkbox_items() {
+        match parse_line("- [X] a checked item [1/2]", &TEST_STATES) {
+            Line::ListItem(item) => {
+                assert_eq!(item.checkbox, Some(Checkbox::Checked));
+                assert_eq!(item.content, "a checked item [1/2]");
+                assert!(item.statistics.is_some());
+            }
+            line => panic!("expected a list item, found: {:?}", line),
+        }
+        match parse_line("+ [-]", &TEST_STATES) {
+            Line::ListItem(item) => {
+                assert_eq!(item.checkbox, Some(Checkbox::Partial));
+                assert_eq!(format!("{}", item), "+ [-]\n");
+            }
+            line => panic!("expected a list item, found: {:?}", line),
+        }
+        match parse_line("1. [X]not a checkbox", &TEST_STATES) {
+            Line::ListItem(item) => assert_eq!(item.checkbox, None),
+            line => panic!("expected a list item, found: {:?}", line),
+        }
+    }
+
+    #[test]
+    fn test_description_items() {
+        match parse_line("- [ ] term :: the definition", &TEST_STATES) {
+            Line::ListItem(item) => {
+                assert_eq!(item.kind(), ListKind::Description);
+                assert_eq!(item.checkbox, Some(Checkbox::Unchecked));
+                assert_eq!(item.tag, Some("term"));
+                assert_eq!(item.content, "the definition");
+                assert_eq!(format!("{}", item), "- [ ] term :: the definition\n");
+            }
+            line => panic!("expected a list item, found: {:?}", line),
+        }
+        match parse_line("+ empty ::", &TEST_STATES) {
+            Line::ListItem(item) => {
+                assert_eq!(item.tag, Some("empty"));
+                assert_eq!(format!("{}", item), "+ empty ::\n");
+            }
+            line => panic!("expected a list item, found: {:?}", line),
+        }
+        match parse_line("1. ordered :: is not a description", &TEST_STATES) {
+            Line::ListItem(item) => assert_eq!(item.kind(), ListKind::Ordered),
+            line => panic!("expected a list item, found: {:?}", line),
+        }
+        match parse_line("- a::b is not a description", &TEST_STATES) {
+            Line::ListItem(item) => assert_eq!(item.kind(), ListKind::Unordered),
+            line => panic!("expected a list item, found: {:?}", line),
+        }
+    }
+
+    #[test]
+    fn test_not_list_items() {
+        assert!(NOT_LIST_ITEMS
+            .iter()
+            .all(|line: &&str| matches!(parse_line(line, &TEST_STATES), Line::Text(_))))
+    }
+
+    #[test]
+    fn test_counter_items() {
+        match parse_line_with("c) [@5] [X] fifth", &TEST_STATES, true) {
+            Line::ListItem(item) => {
+                assert_eq!(item.bullet, Bullet::Alphabetic('c', Delimiter::Parenthesis));
+                assert_eq!(item.counter, Some(5));
+                assert_eq!(item.checkbox, Some(Checkbox::Checked));
+                assert_eq!(item.content, "fifth");
+                assert_eq!(format!("{}", item), "c) [@5] [X] fifth\n");
+            }
+            line => panic!("expected a list item, found: {:?}", line),
+        }
+        match parse_line("1. [@b] second", &TEST_STATES) {
+            Line::ListItem(mut item) => {
+                assert_eq!(item.counter, Some(2));
+                assert_eq!(format!("{}", item), "1. [@b] second\n");
+                item.counter = Some(3);
+                assert_eq!(format!("{}", item), "1. [@3] second\n");
+            }
+            line => panic!("expected a list item, found: {:?}", line),
+        }
+        match parse_line("- [@5] is not a counter", &TEST_STATES) {
+            Line::ListItem(item) => assert_eq!(item.counter, None),
+            line => panic!("expected a list item, found: {:?}", line),
+        }
+    }
+
+    #[test]
+    fn test_bullet_matches() {
+        let minus = Bullet::Minus;
+        let numeric = Bullet::Numeric(1, Delimiter::Period);
+        assert!(Bullet::Plus.matches(&minus));
+        assert!(Bullet::Star.matches(&minus));
+        assert!(Bullet::Alphabetic('b', Delimiter::Parenthesis).matches(&numeric));
+        assert!(!numeric.matches(&minus));
+        assert!(!minus.matches(&numeric));
+    }
+
+    #[test]
+    fn test_alphabetical_list_items() {
+        for line in ALPHABETICAL_LIST_ITEMS.iter() {
+            assert!(matches!(parse_line(line, &TEST_STATES), Line::Text(_)));
+            assert!(matches!(
+                parse_line_with(line, &TEST_STATES, true),
+                Line::ListItem(_)
+            ));
+        }
+    }
+
     #[test]
     fn test_good_list_items() {
         assert!(GOOD_LIST_ITEMS.iter().all(|line: &&str| {
@@ -0,0 +1,171 @@
+use std::{fmt, fmt::Display};
+
+use crate::timestamp::Timestamp;
+
+const SCHEDULED: &str = "SCHEDULED:";
+const DEADLINE: &str = "DEADLINE:";
+const CLOSED: &str = "CLOSED:";
+
+/// the planning line directly below a headline, e.g.
+/// `DEADLINE: <2020-03-01 Sun> SCHEDULED: <2020-02-28 Fri>`
+#[derive(Debug, Default, PartialEq)]
+pub struct Planning {
+    pub scheduled: Option<Timestamp>,
+    pub deadline: Option<Timestamp>,
+    pub closed: Option<Timestamp>,
+    /// the keywords in the order they were written,
+    /// so the line is printed the same way
+    pub order: Vec<PlanningKeyword>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlanningKeyword {
+    Scheduled,
+    Deadline,
+    Closed,
+}
+
+impl Planning {
+    /// parse a planning line.
+    /// returns `None` if the line contains anything other than
+    /// planning keywords followed by timestamps.
+    pub fn parse(line: &str) -> Option<Planning> {
+        let mut planning = Planning::default();
+        let mut text = line.trim();
+
+        if text.is_empty() {
+            return None;
+        }
+
+        while !text.is_empty() {
+            let (keyword, rem) = if let Some(rem) = text.strip_prefix(SCHEDULED) {
+                (PlanningKeyword::Scheduled, rem)
+            } else if let Some(rem) = text.strip_prefix(DEADLINE) {
+                (PlanningKeyword::Deadline, rem)
+            } else if let Some(rem) = text.strip_prefix(CLOSED) {
+                (PlanningKeyword::Closed, rem)
+            } else {
+                return None;
+            };
+
+            let (timestamp, rem) = Timestamp::parse_prefix(rem.trim_start())?;
+            if planning.get_mut(keyword).replace(timestamp).is_some() {
+                // repeated keyword
+                return None;
+            }
+            planning.order.push(keyword);
+            text = rem.trim_start();
+        }
+
+        Some(planning)
+    }
+
+    pub fn get(&self, keyword: PlanningKeyword) -> Option<&Timestamp> {
+        match keyword {
+            PlanningKeyword::Scheduled => self.scheduled.as_ref(),
+            PlanningKeyword::Deadline => self.deadline.as_ref(),
+            PlanningKeyword::Closed => self.closed.as_ref(),
+        }
+    }
+
+    fn get_mut(&mut self, keyword: PlanningKeyword) -> &mut Option<Timestamp> {
+        match keyword {
+            PlanningKeyword::Scheduled => &mut self.scheduled,
+            PlanningKeyword::Deadline => &mut self.deadline,
+            PlanningKeyword::Closed => &mut self.closed,
+        }
+    }
+}
+
+impl PlanningKeyword {
+    fn as_str(self) -> &'static str {
+        match self {
+            PlanningKeyword::Scheduled => SCHEDULED,
+            PlanningKeyword::Deadline => DEADLINE,
+            PlanningKeyword::Closed => CLOSED,
+        }
+    }
+}
+
+impl Display for Planning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // keywords that were set after parsing go after the written ones
+        let added = [
+            PlanningKeyword::Closed,
+            PlanningKeyword::Deadline,
+            PlanningKeyword::Scheduled,
+        ]
+        .iter()
+        .filter(|keyword| !self.order.contains(keyword));
+        let mut first = true;
+        for keyword in self.order.iter().chain(added) {
+            if let Some(timestamp) = self.get(*keyword) {
+                if !first {
+                    write!(f, " ")?;
+                }
+                write!(f, "{} {}", keyword.as_str(), timestamp)?;
+                first = false;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timestamp(s: &str) -> Option<Timestamp> {
+        Some(Timestamp::parse(s).unwrap())
+    }
+
+    #[test]
+    fn parse_planning_lines() {
+        assert_eq!(
+            Planning::parse("  SCHEDULED: <2020-02-28 Fri> DEADLINE: <2020-03-01 Sun +1w>"),
+            Some(Planning {
+                scheduled: timestamp("<2020-02-28 Fri>"),
+                deadline: timestamp("<2020-03-01 Sun +1w>"),
+                closed: None,
+                order: vec![PlanningKeyword::Scheduled, PlanningKeyword::Deadline],
+            })
+        );
+        assert_eq!(
+            Planning::parse("CLOSED: [2020-02-28 Fri 10:12]"),
+            Some(Planning {
+                closed: timestamp("[2020-02-28 Fri 10:12]"),
+                order: vec![PlanningKeyword::Closed],
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            Planning::parse("SCHEDULED: <2020-02-28 Fri>--<2020-03-02 Mon>"),
+            Some(Planning {
+                scheduled: timestamp("<2020-02-28 Fri>--<2020-03-02 Mon>"),
+                order: vec![PlanningKeyword::Scheduled],
+                ..Default::default()
+            })
+        );
+        assert_eq!(Planning::parse("SCHEDULED: tomorrow"), None);
+        assert_eq!(Planning::parse("DEADLINE: <2020-02-28 Fri> and text"), None);
+        assert_eq!(
+            Planning::parse("DEADLINE: <2020-02-28 Fri> DEADLINE: <2020-02-29 Sat>"),
+            None
+        );
+        assert_eq!(Planning::parse(""), None);
+    }
+
+    #[test]
+    fn keep_keyword_order() {
+        let line = "SCHEDULED: <2020-02-28 Fri> DEADLINE: <2020-03-01 Sun>";
+        let mut planning = Planning::parse(line).unwrap();
+        assert_eq!(format!("{}", planning), line);
+
+        planning.closed = timestamp("[2020-02-28 Fri 10:12]");
+        planning.deadline = None;
+        assert_eq!(
+            format!("{}", planning),
+            "SCHEDULED: <2020-02-28 Fri> CLOSED: [2020-02-28 Fri 10:12]"
+        );
+    }
+}
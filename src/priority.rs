@@ -0,0 +1,201 @@
+use std::{cmp::Ordering, fmt, fmt::Display};
+
+use crate::headline::HeadlineGroup;
+
+/// the range of priorities headlines can have,
+/// set with `#+PRIORITIES: highest lowest default`.
+/// priorities are letters, e.g. `A` to `C`, or digits, e.g. `1` to `5`,
+/// and the highest priority has the smallest value.
+/// a priority is a single character, so unlike org, numeric
+/// priorities only go up to `9` and a cookie like `[#10]` is not a priority.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Priorities {
+    highest: char,
+    lowest: char,
+    default: char,
+}
+
+impl Priorities {
+    /// returns `None` if the priorities are not all uppercase letters or all digits,
+    /// or if `default` is not between `highest` and `lowest`
+    pub fn new(highest: char, lowest: char, default: char) -> Option<Priorities> {
+        let same_kind = [lowest, default]
+            .iter()
+            .all(|ch| is_letter(*ch) == is_letter(highest) && is_priority(*ch));
+        if !is_priority(highest) || !same_kind || highest > lowest {
+            return None;
+        }
+        if default < highest || default > lowest {
+            return None;
+        }
+
+        Some(Priorities {
+            highest,
+            lowest,
+            default,
+        })
+    }
+
+    /// parse the value of a `#+PRIORITIES:` keyword, e.g. `A C B` or `1 5 3`
+    pub fn parse(value: &str) -> Option<Priorities> {
+        let mut chars = value.split_whitespace().map(|word| {
+            let mut chars = word.chars();
+            match (chars.next(), chars.next()) {
+                (Some(ch), None) => Some(ch),
+                _ => None,
+            }
+        });
+        let highest = chars.next()??;
+        let lowest = chars.next()??;
+        let default = chars.next()??;
+        if chars.next().is_some() {
+            return None;
+        }
+        Priorities::new(highest, lowest, default)
+    }
+
+    pub fn highest(&self) -> char {
+        self.highest
+    }
+
+    pub fn lowest(&self) -> char {
+        self.lowest
+    }
+
+    pub fn default_priority(&self) -> char {
+        self.default
+    }
+
+    pub fn contains(&self, priority: char) -> bool {
+        (self.highest..=self.lowest).contains(&priority)
+    }
+
+    /// the priority a headline with `priority` is treated as.
+    /// headlines without a priority have the default priority.
+    pub fn effective(&self, priority: Option<char>) -> char {
+        priority.unwrap_or(self.default)
+    }
+
+    /// the next higher priority, or `None` if `priority` is the highest
+    pub fn raise(&self, priority: Option<char>) -> Option<char> {
+        let priority = self.effective(priority);
+        if priority <= self.highest {
+            None
+        } else {
+            char::from_u32(priority as u32 - 1)
+        }
+    }
+
+    /// the next lower priority, or `None` if `priority` is the lowest
+    pub fn lower(&self, priority: Option<char>) -> Option<char> {
+        let priority = self.effective(priority);
+        if priority >= self.lowest {
+            None
+        } else {
+            char::from_u32(priority as u32 + 1)
+        }
+    }
+
+    /// order priorities from highest to lowest
+    pub fn compare(&self, a: Option<char>, b: Option<char>) -> Ordering {
+        self.effective(a).cmp(&self.effective(b))
+    }
+
+    /// sort headlines from highest to lowest priority.
+    /// the sort is stable, so headlines with the same priority keep their order.
+    pub fn sort(&self, headlines: &mut [&HeadlineGroup]) {
+        headlines.sort_by(|a, b| self.compare(a.headline.priority(), b.headline.priority()));
+    }
+}
+
+fn is_letter(ch: char) -> bool {
+    ch.is_ascii_uppercase()
+}
+
+/// check if `ch` can be a priority, an uppercase letter or a digit
+pub fn is_priority(ch: char) -> bool {
+    ch.is_ascii_uppercase() || ch.is_ascii_digit()
+}
+
+impl Default for Priorities {
+    fn default() -> Self {
+        Priorities {
+            highest: 'A',
+            lowest: 'C',
+            default: 'B',
+        }
+    }
+}
+
+/// formats like the value of a `#+PRIORITIES:` keyword
+impl Display for Priorities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.highest, self.lowest, self.default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_priorities() {
+        assert_eq!(Priorities::parse("A C B"), Some(Priorities::default()));
+        assert_eq!(Priorities::parse("1 5 3"), Priorities::new('1', '5', '3'));
+        assert_eq!(Priorities::parse("A E"), None);
+        assert_eq!(Priorities::parse("C A B"), None);
+        assert_eq!(Priorities::parse("A C 2"), None);
+        assert_eq!(Priorities::parse("A C D"), None);
+        assert_eq!(Priorities::parse("AA C B"), None);
+        assert_eq!(Priorities::parse("1 10 5"), None);
+        assert_eq!(format!("{}", Priorities::parse("1 5 3").unwrap()), "1 5 3");
+    }
+
+    #[test]
+    fn compare_priorities() {
+        let priorities = Priorities::default();
+        assert_eq!(priorities.compare(Some('A'), None), Ordering::Less);
+        assert_eq!(priorities.compare(Some('B'), None), Ordering::Equal);
+        assert_eq!(priorities.compare(Some('C'), Some('A')), Ordering::Greater);
+        assert_eq!(priorities.raise(None), Some('A'));
+        assert_eq!(priorities.raise(Some('A')), None);
+        assert_eq!(priorities.lower(Some('B')), Some('C'));
+        assert_eq!(priorities.lower(Some('C')), None);
+        assert!(priorities.contains('C'));
+        assert!(!priorities.contains('D'));
+    }
+
+    #[test]
+    fn configured_priorities() {
+        let priorities = Priorities::parse("1 5 3").unwrap();
+        let config = crate::config::Builder::default()
+            .file_path("tasks.org".to_string())
+            .priorities(priorities)
+            .build()
+            .unwrap();
+        let document = crate::parser::parse_org_text_with_config("* [#1] task", &config).unwrap();
+        assert_eq!(document.priorities(), priorities);
+
+        let text = "#+PRIORITIES: A E C\n* [#A] task";
+        let document = crate::parser::parse_org_text_with_config(text, &config).unwrap();
+        assert_eq!(document.priorities(), Priorities::parse("A E C").unwrap());
+    }
+
+    #[test]
+    fn sort_headlines() {
+        let text = "* low [#C]
+* TODO [#C] low
+* none
+* DONE [#A] high
+";
+        let document = crate::parser::parse_org_text(text, vec!["TODO", "DONE"]).unwrap();
+        let mut headlines: Vec<_> = document.headlines().collect();
+        Priorities::default().sort(&mut headlines);
+
+        let titles: Vec<_> = headlines
+            .iter()
+            .map(|headline| headline.headline.title())
+            .collect();
+        assert_eq!(titles, vec!["high", "low [#C]", "none", "low"]);
+    }
+}
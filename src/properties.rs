@@ -0,0 +1,202 @@
+use std::{borrow::Cow, fmt, fmt::Display};
+
+pub const PROPERTIES: &str = "PROPERTIES";
+pub const DRAWER_END: &str = ":END:";
+
+/// an ordered list of properties from a `:PROPERTIES:` drawer.
+/// entries are kept in the order they appear in the file,
+/// including `:KEY+:` entries, so the drawer can be reprinted.
+#[derive(Debug, Default)]
+pub struct Properties<'t> {
+    entries: Vec<Property<'t>>,
+    /// the `:PROPERTIES:` and `:END:` lines as they were written
+    lines: Option<(&'t str, &'t str)>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Property<'t> {
+    pub key: &'t str,
+    pub value: &'t str,
+    /// `true` for `:KEY+:` entries which extend the previous value
+    pub append: bool,
+    /// the line the property was parsed from
+    pub raw: Option<&'t str>,
+}
+
+impl<'t> Properties<'t> {
+    pub fn push(&mut self, property: Property<'t>) {
+        self.entries.push(property);
+    }
+
+    /// keep the lines that open and close the drawer
+    /// so that they are printed as they were written
+    pub fn set_lines(&mut self, begin: &'t str, end: &'t str) {
+        self.lines = Some((begin, end));
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Property<'t>> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// get the value of `key`, ignoring case.
+    /// values from `:KEY+:` entries are joined to the
+    /// preceding value with a space.
+    pub fn get(&self, key: &str) -> Option<Cow<'t, str>> {
+        self.entries
+            .iter()
+            .filter(|property| property.key.eq_ignore_ascii_case(key))
+            .fold(None, |value: Option<Cow<'t, str>>, property| {
+                match (value, property.append) {
+                    (Some(value), true) if property.value.is_empty() => Some(value),
+                    (Some(value), true) if value.is_empty() => Some(property.value.into()),
+                    (Some(value), true) => Some(format!("{} {}", value, property.value).into()),
+                    _ => Some(property.value.into()),
+                }
+            })
+    }
+
+    /// iterate over the distinct keys in the order they first appear
+    pub fn keys(&self) -> impl Iterator<Item = &'t str> + '_ {
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(move |(i, property)| {
+                !self.entries[..*i]
+                    .iter()
+                    .any(|other| other.key.eq_ignore_ascii_case(property.key))
+            })
+            .map(|(_, property)| property.key)
+    }
+}
+
+impl<'t> Property<'t> {
+    /// parse a `:KEY: value` line.
+    /// returns `None` if the line is not a property.
+    pub fn parse(raw: &'t str) -> Option<Property<'t>> {
+        let line = raw.trim();
+        if !line.starts_with(':') {
+            return None;
+        }
+
+        let (_, rem) = line.split_at(1);
+        let end = rem.find(':')?;
+        let (key, value) = rem.split_at(end);
+        let (_, value) = value.split_at(1);
+
+        if !value.is_empty() && !value.starts_with(char::is_whitespace) {
+            return None;
+        }
+
+        let (key, append) = match key.strip_suffix('+') {
+            Some(key) => (key, true),
+            None => (key, false),
+        };
+
+        if key.is_empty() || key.contains(char::is_whitespace) || key.eq_ignore_ascii_case("END") {
+            return None;
+        }
+
+        Some(Property {
+            key,
+            value: value.trim(),
+            append,
+            raw: Some(raw),
+        })
+    }
+}
+
+impl<'t> Display for Property<'t> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(raw) = self.raw {
+            return write!(f, "{}", raw);
+        }
+        let plus = if self.append { "+" } else { "" };
+        if self.value.is_empty() {
+            write!(f, ":{}{}:", self.key, plus)
+        } else {
+            write!(f, ":{}{}: {}", self.key, plus, self.value)
+        }
+    }
+}
+
+impl<'t> Display for Properties<'t> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (begin, end) = match self.lines {
+            Some((begin, end)) => (Cow::Borrowed(begin), end),
+            None => (Cow::Owned(format!(":{}:", PROPERTIES)), DRAWER_END),
+        };
+        writeln!(f, "{}", begin)?;
+        for property in self.iter() {
+            writeln!(f, "{}", property)?;
+        }
+        writeln!(f, "{}", end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_property_lines() {
+        assert_eq!(
+            Property::parse(":ID:       abc-123"),
+            Some(Property {
+                key: "ID",
+                value: "abc-123",
+                append: false,
+                raw: Some(":ID:       abc-123"),
+            })
+        );
+        assert_eq!(
+            Property::parse(":VAR+: b"),
+            Some(Property {
+                key: "VAR",
+                value: "b",
+                append: true,
+                raw: Some(":VAR+: b"),
+            })
+        );
+        assert_eq!(
+            Property::parse(":EMPTY:"),
+            Some(Property {
+                key: "EMPTY",
+                value: "",
+                append: false,
+                raw: Some(":EMPTY:"),
+            })
+        );
+        assert_eq!(Property::parse(":END:"), None);
+        assert_eq!(Property::parse("not a property"), None);
+        assert_eq!(Property::parse(":no:space"), None);
+    }
+
+    #[test]
+    fn accumulate_values() {
+        let mut properties = Properties::default();
+        for line in &[":VAR: a", ":ID: x", ":var+: b", ":VAR+: c"] {
+            properties.push(Property::parse(line).unwrap());
+        }
+
+        assert_eq!(properties.get("var").as_deref(), Some("a b c"));
+        assert_eq!(properties.get("ID").as_deref(), Some("x"));
+        assert_eq!(properties.get("missing"), None);
+        assert_eq!(properties.keys().collect::<Vec<_>>(), vec!["VAR", "ID"]);
+
+        let property = Property::parse("  :ID:   x  ").unwrap();
+        assert_eq!(format!("{}", property), "  :ID:   x  ");
+        let property = Property {
+            raw: None,
+            ..property
+        };
+        assert_eq!(format!("{}", property), ":ID: x");
+    }
+}
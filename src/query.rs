@@ -0,0 +1,521 @@
+//! search headlines with org's tag and property match syntax, e.g.
+//! `+work-boss|laptop`, `TODO="NEXT"+LEVEL=2` or `{^proj}/!-WAITING`
+
+use std::{cmp::Ordering, iter::Peekable, str::Chars};
+
+use regex::Regex;
+
+use crate::{
+    error::{OrgError, Result},
+    headline::HeadlineGroup,
+    object::Document,
+    priority::Priorities,
+    timestamp::Timestamp,
+};
+
+/// a parsed match expression.
+/// the part before `/` matches tags and properties,
+/// the part after matches todo keywords.
+#[derive(Debug)]
+pub struct Query {
+    /// alternatives separated by `|`, each a list of terms that must all match
+    tags: Vec<Vec<Term>>,
+    todo: Vec<Vec<Term>>,
+    /// `/!` only matches headlines with an active todo keyword
+    active_only: bool,
+}
+
+#[derive(Debug)]
+struct Term {
+    negated: bool,
+    matcher: Matcher,
+}
+
+#[derive(Debug)]
+enum Matcher {
+    Tag(String),
+    TagRegex(Regex),
+    Todo(String),
+    TodoRegex(Regex),
+    Property { key: String, op: Op, value: Value },
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum Op {
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+}
+
+#[derive(Debug)]
+enum Value {
+    Number(f64),
+    String(String),
+    Regex(Regex),
+    /// a quoted timestamp, e.g. `"<2020-02-28>"`
+    Time(Timestamp),
+    /// an unquoted duration in minutes, e.g. `1:30`
+    Duration(f64),
+}
+
+impl Query {
+    pub fn parse(s: &str) -> Result<Query> {
+        let mut parser = Parser {
+            chars: s.chars().peekable(),
+        };
+
+        let tags = parser.parse_alternatives(false)?;
+        let mut active_only = false;
+        let mut todo = Vec::new();
+        if parser.chars.next_if_eq(&'/').is_some() {
+            active_only = parser.chars.next_if_eq(&'!').is_some();
+            todo = parser.parse_alternatives(true)?;
+        }
+
+        match parser.chars.next() {
+            Some(ch) => Err(query_error(&format!("unexpected `{}` in query: {}", ch, s))),
+            None => Ok(Query {
+                tags,
+                todo,
+                active_only,
+            }),
+        }
+    }
+
+    /// check if `headline` in `document` matches this query
+    pub fn matches(&self, headline: &HeadlineGroup, document: &Document) -> bool {
+        self.matches_with(headline, document, document.priorities())
+    }
+
+    fn matches_with(
+        &self,
+        headline: &HeadlineGroup,
+        document: &Document,
+        priorities: Priorities,
+    ) -> bool {
+        let context = Context {
+            headline,
+            document,
+            priorities,
+            tags: headline.effective_tags(),
+        };
+
+        if self.active_only
+            && !headline
+                .headline
+                .status()
+                .map(|status| document.status_labels.is_active(status))
+                .unwrap_or(false)
+        {
+            return false;
+        }
+
+        matches_alternatives(&self.tags, &context) && matches_alternatives(&self.todo, &context)
+    }
+
+    /// all headlines in `document` that match this query
+    pub fn search<'t>(
+        &'t self,
+        document: &'t Document<'t>,
+    ) -> impl Iterator<Item = &'t HeadlineGroup<'t>> {
+        let priorities = document.priorities();
+        document
+            .headlines()
+            .filter(move |headline| self.matches_with(headline, document, priorities))
+    }
+}
+
+impl std::str::FromStr for Query {
+    type Err = OrgError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Query::parse(s)
+    }
+}
+
+/// an empty list of alternatives matches everything
+fn matches_alternatives(alternatives: &[Vec<Term>], context: &Context) -> bool {
+    alternatives.is_empty()
+        || alternatives
+            .iter()
+            .any(|terms| terms.iter().all(|term| term.matches(context)))
+}
+
+struct Context<'a, 't> {
+    headline: &'a HeadlineGroup<'t>,
+    document: &'a Document<'t>,
+    priorities: Priorities,
+    tags: Vec<&'t str>,
+}
+
+impl Term {
+    fn matches(&self, context: &Context) -> bool {
+        self.matcher.matches(context) != self.negated
+    }
+}
+
+impl Matcher {
+    fn matches(&self, context: &Context) -> bool {
+        let status = context.headline.headline.status();
+        match self {
+            Matcher::Tag(tag) => {
+                let tags = context.document.tag_groups.expand(tag);
+                context.tags.iter().any(|tag| tags.contains(tag))
+            }
+            Matcher::TagRegex(regex) => context.tags.iter().any(|tag| regex.is_match(tag)),
+            Matcher::Todo(todo) => status == Some(todo.as_str()),
+            Matcher::TodoRegex(regex) => status.map(|s| regex.is_match(s)).unwrap_or(false),
+            Matcher::Property { key, op, value } => match property(context, key) {
+                Some(property) => value.compare(&property, *op),
+                // missing properties only differ from values
+                None => *op == Op::NotEqual,
+            },
+        }
+    }
+}
+
+/// the value of a property or a special property like `TODO` or `LEVEL`
+fn property(context: &Context, key: &str) -> Option<String> {
+    let headline = &context.headline.headline;
+    let planning = context.headline.planning();
+    let timestamp = |timestamp: Option<&Timestamp>| timestamp.map(|t| t.to_string());
+    match key.to_ascii_uppercase().as_str() {
+        "TODO" => Some(headline.status().unwrap_or("").to_string()),
+        "LEVEL" => Some(headline.level().to_string()),
+        "PRIORITY" => Some(
+            context
+                .priorities
+                .effective(headline.priority())
+                .to_string(),
+        ),
+        "SCHEDULED" => timestamp(planning.and_then(|p| p.scheduled.as_ref())),
+        "DEADLINE" => timestamp(planning.and_then(|p| p.deadline.as_ref())),
+        "CLOSED" => timestamp(planning.and_then(|p| p.closed.as_ref())),
+        _ => context
+            .headline
+            .property(key)
+            .map(|value| value.into_owned()),
+    }
+}
+
+impl Value {
+    fn compare(&self, property: &str, op: Op) -> bool {
+        let ordering = match self {
+            Value::Regex(regex) => {
+                return match op {
+                    Op::Equal => regex.is_match(property),
+                    Op::NotEqual => !regex.is_match(property),
+                    _ => false,
+                }
+            }
+            Value::String(value) => Some(property.cmp(value.as_str())),
+            Value::Number(value) => property
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .and_then(|property| property.partial_cmp(value)),
+            Value::Duration(value) => {
+                parse_duration(property.trim()).and_then(|property| property.partial_cmp(value))
+            }
+            Value::Time(value) => Timestamp::parse(property.trim())
+                .map(|property| time_key(&property).cmp(&time_key(value))),
+        };
+
+        match ordering {
+            Some(ordering) => op.test(ordering),
+            None => op == Op::NotEqual,
+        }
+    }
+}
+
+/// the date and time of a timestamp for comparisons
+fn time_key(timestamp: &Timestamp) -> (i64, u32) {
+    (
+        timestamp.date.days(),
+        timestamp.time.map(|time| time.minutes()).unwrap_or(0),
+    )
+}
+
+/// parse a duration like `1:30` or `90` into minutes
+fn parse_duration(s: &str) -> Option<f64> {
+    match s.split_once(':') {
+        Some((hours, minutes)) => {
+            let hours: u64 = hours.parse().ok()?;
+            let minutes: u64 = minutes.parse().ok()?;
+            let minutes = hours.checked_mul(60)?.checked_add(minutes)?;
+            Some(minutes as f64)
+        }
+        None => s.parse().ok(),
+    }
+}
+
+impl Op {
+    fn test(self, ordering: Ordering) -> bool {
+        match self {
+            Op::Equal => ordering == Ordering::Equal,
+            Op::NotEqual => ordering != Ordering::Equal,
+            Op::Less => ordering == Ordering::Less,
+            Op::LessEqual => ordering != Ordering::Greater,
+            Op::Greater => ordering == Ordering::Greater,
+            Op::GreaterEqual => ordering != Ordering::Less,
+        }
+    }
+}
+
+struct Parser<'s> {
+    chars: Peekable<Chars<'s>>,
+}
+
+impl<'s> Parser<'s> {
+    /// parse terms separated by `|` up to a `/` or the end
+    fn parse_alternatives(&mut self, todo: bool) -> Result<Vec<Vec<Term>>> {
+        let mut alternatives = Vec::new();
+        let mut terms = Vec::new();
+        loop {
+            match self.chars.peek() {
+                None | Some('/') => break,
+                Some('|') => {
+                    self.chars.next();
+                    alternatives.push(std::mem::take(&mut terms));
+                }
+                Some('&') | Some(' ') => {
+                    self.chars.next();
+                }
+                Some(_) => terms.push(self.parse_term(todo)?),
+            }
+        }
+        if !terms.is_empty() || !alternatives.is_empty() {
+            alternatives.push(terms);
+        }
+        Ok(alternatives)
+    }
+
+    fn parse_term(&mut self, todo: bool) -> Result<Term> {
+        let negated = match self.chars.next_if(|ch| *ch == '+' || *ch == '-') {
+            Some(sign) => sign == '-',
+            None => false,
+        };
+
+        if self.chars.next_if_eq(&'{').is_some() {
+            let regex = self.parse_regex()?;
+            let matcher = if todo {
+                Matcher::TodoRegex(regex)
+            } else {
+                Matcher::TagRegex(regex)
+            };
+            return Ok(Term { negated, matcher });
+        }
+
+        let mut name = String::new();
+        while let Some(ch) = self.chars.next_if(|ch| is_name_char(*ch)) {
+            name.push(ch);
+        }
+        if name.is_empty() {
+            let message = match self.chars.peek() {
+                Some(ch) => format!("unexpected `{}` in query", ch),
+                None => "expected a tag at the end of the query".to_string(),
+            };
+            return Err(query_error(&message));
+        }
+
+        let matcher = match self.parse_op()? {
+            Some(op) => Matcher::Property {
+                key: name,
+                op,
+                value: self.parse_value(op)?,
+            },
+            None if todo => Matcher::Todo(name),
+            None => Matcher::Tag(name),
+        };
+        Ok(Term { negated, matcher })
+    }
+
+    fn parse_op(&mut self) -> Result<Option<Op>> {
+        let first = match self.chars.next_if(|ch| matches!(ch, '=' | '<' | '>' | '!')) {
+            Some(first) => first,
+            None => return Ok(None),
+        };
+        let second = self.chars.next_if(|ch| matches!(ch, '=' | '>'));
+        let op = match (first, second) {
+            ('=', Some('=')) | ('=', None) => Op::Equal,
+            ('<', Some('>')) | ('!', Some('=')) => Op::NotEqual,
+            ('<', Some('=')) => Op::LessEqual,
+            ('<', None) => Op::Less,
+            ('>', Some('=')) => Op::GreaterEqual,
+            ('>', None) => Op::Greater,
+            (first, second) => {
+                let op: String = std::iter::once(first).chain(second).collect();
+                return Err(query_error(&format!("unknown operator `{}` in query", op)));
+            }
+        };
+        Ok(Some(op))
+    }
+
+    fn parse_value(&mut self, op: Op) -> Result<Value> {
+        if self.chars.next_if_eq(&'{').is_some() {
+            if op != Op::Equal && op != Op::NotEqual {
+                return Err(query_error(
+                    "regular expressions only match with `=` or `<>`",
+                ));
+            }
+            return self.parse_regex().map(Value::Regex);
+        }
+
+        if self.chars.next_if_eq(&'"').is_some() {
+            let mut value = String::new();
+            loop {
+                match self.chars.next() {
+                    Some('"') => break,
+                    Some(ch) => value.push(ch),
+                    None => return Err(query_error("unterminated string in query")),
+                }
+            }
+            return Ok(match Timestamp::parse(&value) {
+                Some(timestamp) if value.starts_with('<') || value.starts_with('[') => {
+                    Value::Time(timestamp)
+                }
+                _ => Value::String(value),
+            });
+        }
+
+        // only a leading `-` is a sign, a later one starts the next term
+        let mut value: String = self.chars.next_if_eq(&'-').into_iter().collect();
+        while let Some(ch) = self
+            .chars
+            .next_if(|ch| ch.is_ascii_digit() || matches!(ch, '.' | ':'))
+        {
+            value.push(ch);
+        }
+        if value.contains(':') {
+            parse_duration(&value).map(Value::Duration)
+        } else {
+            value.parse().ok().map(Value::Number)
+        }
+        .ok_or_else(|| query_error(&format!("expected a value in query, found `{}`", value)))
+    }
+
+    /// parse a regular expression up to the closing `}`
+    fn parse_regex(&mut self) -> Result<Regex> {
+        let mut pattern = String::new();
+        loop {
+            match self.chars.next() {
+                Some('}') => break,
+                Some(ch) => pattern.push(ch),
+                None => return Err(query_error("unterminated `{` in query")),
+            }
+        }
+        Regex::new(&pattern).map_err(|err| query_error(&err.to_string()))
+    }
+}
+
+fn is_name_char(ch: char) -> bool {
+    ch.is_alphanumeric() || matches!(ch, '_' | '@' | '#' | '%')
+}
+
+fn query_error(message: &str) -> OrgError {
+    OrgError::ParseError(None, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_org_text;
+
+    const QUERY_TEXT: &str = "#+TODO: TODO NEXT WAITING | DONE
+#+TAGS: [ Project : projA projB ]
+* NEXT [#A] call the office :work:
+* TODO review :work:boss:
+:PROPERTIES:
+:EFFORT: 1:30
+:END:
+* projects :projA:
+** DONE release
+SCHEDULED: <2020-02-28 Fri>
+:PROPERTIES:
+:EFFORT: 0:30
+:END:
+** WAITING feedback :laptop:
+SCHEDULED: <2020-03-05 Thu>
+";
+
+    fn search(query: &str) -> Vec<String> {
+        let document = parse_org_text(QUERY_TEXT, vec!["TODO"]).unwrap();
+        let query = Query::parse(query).unwrap();
+        document
+            .search(&query)
+            .map(|headline| headline.headline.title().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn match_tags() {
+        assert_eq!(search("+work-boss"), vec!["call the office"]);
+        assert_eq!(search("work-boss"), vec!["call the office"]);
+        assert_eq!(search("boss|laptop"), vec!["review", "feedback"]);
+        assert_eq!(search("Project"), vec!["projects", "release", "feedback"]);
+        assert_eq!(search("{^proj}-laptop"), vec!["projects", "release"]);
+        assert_eq!(search("").len(), 5);
+    }
+
+    #[test]
+    fn match_properties() {
+        assert_eq!(search(r#"TODO="NEXT""#), vec!["call the office"]);
+        assert_eq!(search(r#"PRIORITY<"B""#), vec!["call the office"]);
+        assert_eq!(search("EFFORT>1:00"), vec!["review"]);
+        assert_eq!(
+            search("EFFORT<>1:30"),
+            vec!["call the office", "projects", "release", "feedback"]
+        );
+        assert_eq!(search("LEVEL=2"), vec!["release", "feedback"]);
+        assert_eq!(search("LEVEL=2&TODO={WAIT}"), vec!["feedback"]);
+        assert_eq!(search(r#"SCHEDULED>="<2020-03-01>""#), vec!["feedback"]);
+        assert_eq!(search("LEVEL>-1-projA"), vec!["call the office", "review"]);
+    }
+
+    #[test]
+    fn match_properties_and_tags() {
+        assert_eq!(search("LEVEL=2-laptop"), vec!["release"]);
+        assert_eq!(search("LEVEL=2+laptop"), vec!["feedback"]);
+        assert_eq!(search("EFFORT>1:00-boss"), Vec::<String>::new());
+        assert_eq!(search("EFFORT>0:10+work"), vec!["review"]);
+        assert_eq!(search("work-EFFORT>1:00"), vec!["call the office"]);
+    }
+
+    #[test]
+    fn match_todo_keywords() {
+        assert_eq!(search("work/NEXT"), vec!["call the office"]);
+        assert_eq!(
+            search("/-NEXT-TODO"),
+            vec!["projects", "release", "feedback"]
+        );
+        assert_eq!(search("projA/!"), vec!["feedback"]);
+        assert_eq!(search("/DONE|{^WAIT}"), vec!["release", "feedback"]);
+    }
+
+    #[test]
+    fn large_durations() {
+        assert_eq!(parse_duration("4294967295:00"), Some(4294967295.0 * 60.0));
+        assert_eq!(parse_duration("18446744073709551615:00"), None);
+        let document = parse_org_text(
+            "* huge\n:PROPERTIES:\n:EFFORT: 99999999999999999999:00\n:END:\n",
+            vec!["TODO"],
+        )
+        .unwrap();
+        let query = Query::parse("EFFORT>1:00").unwrap();
+        assert_eq!(document.search(&query).count(), 0);
+    }
+
+    #[test]
+    fn query_errors() {
+        assert!(Query::parse("work+").is_err());
+        assert!(Query::parse("{unterminated").is_err());
+        assert!(Query::parse(r#"TODO="NEXT"#).is_err());
+        assert!(Query::parse("EFFORT<{1}").is_err());
+        assert!(Query::parse("LEVEL=two").is_err());
+        assert!(Query::parse("work)").is_err());
+        assert!(Query::parse("LEVEL!2").is_err());
+    }
+}
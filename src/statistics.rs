@@ -0,0 +1,135 @@
+use std::{fmt, fmt::Display, ops::Range};
+
+/// a statistics cookie, e.g. `[1/3]` or `[33%]`.
+/// empty cookies, `[/]` and `[%]`, are placeholders to be filled in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Cookie {
+    Fraction(Option<(usize, usize)>),
+    Percent(Option<usize>),
+}
+
+/// a statistics cookie found in some text,
+/// e.g. a headline title or a list item
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statistics {
+    range: Range<usize>,
+    pub cookie: Cookie,
+}
+
+impl Cookie {
+    /// parse a cookie including the brackets
+    pub fn parse(s: &str) -> Option<Cookie> {
+        let inner = s.strip_prefix('[')?.strip_suffix(']')?;
+
+        if let Some(percent) = inner.strip_suffix('%') {
+            if percent.is_empty() {
+                Some(Cookie::Percent(None))
+            } else {
+                parse_number(percent).map(|percent| Cookie::Percent(Some(percent)))
+            }
+        } else {
+            let (done, total) = inner.split_at(inner.find('/')?);
+            let total = &total[1..];
+            if done.is_empty() && total.is_empty() {
+                Some(Cookie::Fraction(None))
+            } else {
+                Some(Cookie::Fraction(Some((
+                    parse_number(done)?,
+                    parse_number(total)?,
+                ))))
+            }
+        }
+    }
+
+    /// a cookie of the same kind for the given counts
+    pub fn with_counts(&self, done: usize, total: usize) -> Cookie {
+        match self {
+            Cookie::Fraction(_) => Cookie::Fraction(Some((done, total))),
+            Cookie::Percent(_) if total == 0 => Cookie::Percent(Some(0)),
+            Cookie::Percent(_) => Cookie::Percent(Some(done * 100 / total)),
+        }
+    }
+}
+
+impl Statistics {
+    /// find the first statistics cookie in `text`
+    pub fn find(text: &str) -> Option<Statistics> {
+        text.match_indices('[').find_map(|(start, _)| {
+            let end = start + text[start..].find(']')? + 1;
+            Cookie::parse(&text[start..end]).map(|cookie| Statistics {
+                range: start..end,
+                cookie,
+            })
+        })
+    }
+
+    pub fn update(&mut self, done: usize, total: usize) {
+        self.cookie = self.cookie.with_counts(done, total);
+    }
+
+    /// write `text` with the original cookie replaced by the current one
+    pub fn write_text(&self, f: &mut fmt::Formatter<'_>, text: &str) -> fmt::Result {
+        write!(
+            f,
+            "{}{}{}",
+            &text[..self.range.start],
+            self.cookie,
+            &text[self.range.end..]
+        )
+    }
+}
+
+fn parse_number(s: &str) -> Option<usize> {
+    if s.chars().all(|ch| ch.is_ascii_digit()) {
+        s.parse().ok()
+    } else {
+        None
+    }
+}
+
+impl Display for Cookie {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Cookie::Fraction(Some((done, total))) => write!(f, "[{}/{}]", done, total),
+            Cookie::Fraction(None) => write!(f, "[/]"),
+            Cookie::Percent(Some(percent)) => write!(f, "[{}%]", percent),
+            Cookie::Percent(None) => write!(f, "[%]"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_cookies() {
+        assert_eq!(Cookie::parse("[1/3]"), Some(Cookie::Fraction(Some((1, 3)))));
+        assert_eq!(Cookie::parse("[/]"), Some(Cookie::Fraction(None)));
+        assert_eq!(Cookie::parse("[33%]"), Some(Cookie::Percent(Some(33))));
+        assert_eq!(Cookie::parse("[%]"), Some(Cookie::Percent(None)));
+        assert_eq!(Cookie::parse("[1/]"), None);
+        assert_eq!(Cookie::parse("[#A]"), None);
+        assert_eq!(Cookie::parse("[X]"), None);
+    }
+
+    #[test]
+    fn update_cookies() {
+        let text = "a [[link]] then [1/3] cookie";
+        let mut statistics = Statistics::find(text).unwrap();
+        assert_eq!(statistics.cookie, Cookie::Fraction(Some((1, 3))));
+
+        statistics.update(2, 4);
+        assert_eq!(statistics.cookie, Cookie::Fraction(Some((2, 4))));
+
+        let mut statistics = Statistics::find("[%]").unwrap();
+        statistics.update(1, 3);
+        assert_eq!(statistics.cookie, Cookie::Percent(Some(33)));
+        statistics.update(2, 3);
+        assert_eq!(statistics.cookie, Cookie::Percent(Some(66)));
+        statistics.update(1, 8);
+        assert_eq!(statistics.cookie, Cookie::Percent(Some(12)));
+        statistics.update(3, 8);
+        assert_eq!(statistics.cookie, Cookie::Percent(Some(37)));
+    }
+}
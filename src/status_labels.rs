@@ -1,13 +1,257 @@
-use crate::error;
+use std::{fmt, fmt::Display};
 
-const STATUS_LABELS: [&'static str; 3] = ["TODO", "STARTED", "DONE"];
+use crate::{error, keyword::Keyword};
 
-/// an owned string that represents status labels
-pub struct StatusLabels(String);
+const STATUS_LABELS: [&str; 3] = ["TODO", "STARTED", "DONE"];
+
+/// the keywords that define todo sequences in a file
+const SEQUENCE_KEYWORDS: [&str; 3] = ["TODO", "SEQ_TODO", "TYP_TODO"];
+
+/// the status labels, or todo keywords, that headlines can have.
+/// labels are grouped into sequences of active labels followed by done labels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatusLabels {
+    sequences: Vec<Sequence>,
+}
+
+/// a todo sequence, e.g. `TODO(t) NEXT | DONE(d!) CANCELLED(c@)`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sequence {
+    pub kind: SequenceKind,
+    labels: Vec<Label>,
+    /// the index of the first done label
+    done_index: usize,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum SequenceKind {
+    /// `#+TODO:` and `#+SEQ_TODO:` keywords are steps in a sequence
+    Sequence,
+    /// `#+TYP_TODO:` keywords are types of tasks
+    Type,
+}
+
+/// a status label with its fast access key and logging markers
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub name: String,
+    /// the key in `TODO(t)`
+    pub key: Option<char>,
+    /// logging when entering the state, `!` or `@` in `DONE(d!)`
+    pub log_enter: Option<Logging>,
+    /// logging when leaving the state, `!` or `@` in `WAIT(w/!)`
+    pub log_leave: Option<Logging>,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Logging {
+    /// `!`: record a timestamp
+    Timestamp,
+    /// `@`: record a note with a timestamp
+    Note,
+}
 
 impl StatusLabels {
+    pub fn new(sequences: Vec<Sequence>) -> Self {
+        StatusLabels { sequences }
+    }
+
+    /// build status labels from the `#+TODO:`, `#+SEQ_TODO:` and `#+TYP_TODO:` keywords.
+    /// returns `None` if there are no todo keywords.
+    pub fn from_keywords<'a, I: IntoIterator<Item = &'a Keyword<'a>>>(
+        keywords: I,
+    ) -> error::Result<Option<StatusLabels>> {
+        let sequences = keywords
+            .into_iter()
+            .filter_map(Sequence::from_keyword)
+            .collect::<error::Result<Vec<_>>>()?;
+
+        if sequences.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(StatusLabels { sequences }))
+        }
+    }
+
+    /// all label names in order
     pub fn iter(&self) -> impl Iterator<Item = &str> {
-        self.0.split(',')
+        self.labels().map(|label| label.name.as_str())
+    }
+
+    pub fn labels(&self) -> impl Iterator<Item = &Label> {
+        self.sequences
+            .iter()
+            .flat_map(|sequence| sequence.labels.iter())
+    }
+
+    pub fn sequences(&self) -> &[Sequence] {
+        &self.sequences
+    }
+
+    pub fn label(&self, name: &str) -> Option<&Label> {
+        self.labels().find(|label| label.name == name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.label(name).is_some()
+    }
+
+    /// check if `name` is a done state
+    pub fn is_done(&self, name: &str) -> bool {
+        self.find(name)
+            .map(|(sequence, i)| i >= sequence.done_index)
+            .unwrap_or(false)
+    }
+
+    /// check if `name` is an active, not done, state
+    pub fn is_active(&self, name: &str) -> bool {
+        self.find(name)
+            .map(|(sequence, i)| i < sequence.done_index)
+            .unwrap_or(false)
+    }
+
+    /// the first label, used when a headline without a status is cycled
+    pub fn first(&self) -> Option<&str> {
+        self.iter().next()
+    }
+
+    /// the label after `name` in its sequence,
+    /// or `None` if `name` is the last label.
+    /// the labels of a type sequence all lead to the first done label.
+    pub fn next(&self, name: &str) -> Option<&str> {
+        let (sequence, i) = self.find(name)?;
+        let next = match sequence.kind {
+            SequenceKind::Type if i < sequence.done_index => sequence.done_index,
+            _ => i + 1,
+        };
+        sequence.labels.get(next).map(|label| label.name.as_str())
+    }
+
+    /// the label before `name` in its sequence,
+    /// or `None` if `name` is the first label
+    pub fn previous(&self, name: &str) -> Option<&str> {
+        let (sequence, i) = self.find(name)?;
+        let previous = match sequence.kind {
+            SequenceKind::Type if i >= sequence.done_index => 0,
+            _ => i.checked_sub(1)?,
+        };
+        sequence
+            .labels
+            .get(previous)
+            .map(|label| label.name.as_str())
+    }
+
+    /// the label with the fast access `key`
+    pub fn fast_access(&self, key: char) -> Option<&str> {
+        self.labels()
+            .find(|label| label.key == Some(key))
+            .map(|label| label.name.as_str())
+    }
+
+    fn find(&self, name: &str) -> Option<(&Sequence, usize)> {
+        self.sequences.iter().find_map(|sequence| {
+            sequence
+                .labels
+                .iter()
+                .position(|label| label.name == name)
+                .map(|i| (sequence, i))
+        })
+    }
+}
+
+impl Sequence {
+    /// parse the sequence defined by a `#+TODO:`, `#+SEQ_TODO:` or `#+TYP_TODO:` keyword.
+    /// returns `None` for other keywords.
+    pub fn from_keyword(keyword: &Keyword) -> Option<error::Result<Sequence>> {
+        let i = SEQUENCE_KEYWORDS.iter().position(|key| keyword.is(key))?;
+        let kind = match i {
+            2 => SequenceKind::Type,
+            _ => SequenceKind::Sequence,
+        };
+        Some(Sequence::parse(kind, keyword.value))
+    }
+
+    /// parse a sequence like `TODO(t) NEXT | DONE(d!)`.
+    /// without a `|`, the last label is the done state.
+    pub fn parse(kind: SequenceKind, s: &str) -> error::Result<Sequence> {
+        let mut labels = Vec::new();
+        let mut done_index = None;
+        for token in s.split(|ch: char| ch.is_whitespace() || ch == ',') {
+            match token {
+                "" => {}
+                "|" => {
+                    done_index.get_or_insert(labels.len());
+                }
+                token => labels.push(token.parse::<Label>()?),
+            }
+        }
+
+        if labels.is_empty() {
+            return Err(error::OrgError::Unexpected(format!(
+                "no labels in todo sequence:\n\t{}",
+                s
+            )));
+        }
+
+        let done_index = done_index.unwrap_or(labels.len() - 1);
+        Ok(Sequence {
+            kind,
+            labels,
+            done_index,
+        })
+    }
+
+    pub fn active(&self) -> &[Label] {
+        &self.labels[..self.done_index]
+    }
+
+    pub fn done(&self) -> &[Label] {
+        &self.labels[self.done_index..]
+    }
+}
+
+impl std::str::FromStr for Label {
+    type Err = error::OrgError;
+
+    /// parse a label like `DONE(d@/!)`
+    fn from_str(s: &str) -> error::Result<Self> {
+        let bad_label = || {
+            error::OrgError::Unexpected(format!("unexpected characters in status label:\n\t{}", s))
+        };
+
+        let (name, options) = match s.find('(') {
+            Some(i) => (&s[..i], s[i + 1..].strip_suffix(')').ok_or_else(bad_label)?),
+            None => (s, ""),
+        };
+
+        if name.is_empty() || !name.chars().all(|ch| ch.is_alphanumeric() || ch == '_') {
+            return Err(bad_label());
+        }
+
+        let (enter, leave) = match options.find('/') {
+            Some(i) => (&options[..i], &options[i + 1..]),
+            None => (options, ""),
+        };
+        let mut enter = enter.chars().peekable();
+        let key = enter.next_if(|ch| ch.is_alphanumeric());
+        let log_enter = parse_logging(enter.next()).map_err(|_| bad_label())?;
+        let log_leave = parse_logging(leave.chars().next()).map_err(|_| bad_label())?;
+
+        Ok(Label {
+            name: name.to_string(),
+            key,
+            log_enter,
+            log_leave,
+        })
+    }
+}
+
+fn parse_logging(marker: Option<char>) -> Result<Option<Logging>, ()> {
+    match marker {
+        None => Ok(None),
+        Some('!') => Ok(Some(Logging::Timestamp)),
+        Some('@') => Ok(Some(Logging::Note)),
+        Some(_) => Err(()),
     }
 }
 
@@ -20,21 +264,172 @@ impl Default for StatusLabels {
 impl std::str::FromStr for StatusLabels {
     type Err = error::OrgError;
 
+    /// parse a single sequence of labels separated
+    /// by whitespace or commas, e.g. `TODO(t),NEXT,|,DONE`
     fn from_str(s: &str) -> error::Result<Self> {
-        // check string for bad characters
-        if s.split(',').all(|s| s.chars().all(char::is_alphanumeric)) {
-            Ok(StatusLabels(String::from(s)))
-        } else {
-            Err(error::OrgError::Unexpected(format!(
-                "unexpected characters in status labels:\n\t{}",
-                s
-            )))
+        Sequence::parse(SequenceKind::Sequence, s).map(|sequence| StatusLabels {
+            sequences: vec![sequence],
+        })
+    }
+}
+
+/// a single sequence where the last label is done
+impl<'a> From<&[&'a str]> for StatusLabels {
+    fn from(labels: &[&'a str]) -> Self {
+        let labels: Vec<Label> = labels
+            .iter()
+            .map(|name| Label {
+                name: name.to_string(),
+                key: None,
+                log_enter: None,
+                log_leave: None,
+            })
+            .collect();
+        let done_index = labels.len().saturating_sub(1);
+
+        StatusLabels {
+            sequences: vec![Sequence {
+                kind: SequenceKind::Sequence,
+                labels,
+                done_index,
+            }],
         }
     }
 }
 
-impl From<&[&'static str]> for StatusLabels {
-    fn from(labels: &[&'static str]) -> Self {
-        StatusLabels(labels.join(",").into())
+impl<'a> From<Vec<&'a str>> for StatusLabels {
+    fn from(labels: Vec<&'a str>) -> Self {
+        StatusLabels::from(labels.as_slice())
+    }
+}
+
+impl Display for Label {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if self.key.is_none() && self.log_enter.is_none() && self.log_leave.is_none() {
+            return Ok(());
+        }
+
+        write!(f, "(")?;
+        if let Some(key) = self.key {
+            write!(f, "{}", key)?;
+        }
+        if let Some(logging) = self.log_enter {
+            write!(f, "{}", logging)?;
+        }
+        if let Some(logging) = self.log_leave {
+            write!(f, "/{}", logging)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl Display for Logging {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Logging::Timestamp => write!(f, "!"),
+            Logging::Note => write!(f, "@"),
+        }
+    }
+}
+
+/// formats like the value of a `#+TODO:` keyword
+impl Display for Sequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let labels = |labels: &[Label]| {
+            labels
+                .iter()
+                .map(|label| label.to_string())
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        write!(f, "{} | {}", labels(self.active()), labels(self.done()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(lines: &[&'static str]) -> StatusLabels {
+        let keywords: Vec<_> = lines
+            .iter()
+            .map(|line| Keyword::parse(line).unwrap())
+            .collect();
+        StatusLabels::from_keywords(&keywords).unwrap().unwrap()
+    }
+
+    #[test]
+    fn parse_sequences() {
+        let labels = parse(&[
+            "#+TODO: TODO(t) NEXT(n!) | DONE(d@/!) CANCELLED(c)",
+            "#+SEQ_TODO: REPORT BUG KNOWNCAUSE FIXED",
+            "#+typ_todo: Fred Sara | COMPLETE",
+        ]);
+
+        assert_eq!(labels.sequences().len(), 3);
+        assert_eq!(
+            format!("{}", labels.sequences()[0]),
+            "TODO(t) NEXT(n!) | DONE(d@/!) CANCELLED(c)"
+        );
+        assert_eq!(labels.sequences()[1].done().len(), 1);
+        assert_eq!(labels.sequences()[2].kind, SequenceKind::Type);
+
+        let done = labels.label("DONE").unwrap();
+        assert_eq!(done.key, Some('d'));
+        assert_eq!(done.log_enter, Some(Logging::Note));
+        assert_eq!(done.log_leave, Some(Logging::Timestamp));
+        assert_eq!(labels.fast_access('n'), Some("NEXT"));
+
+        assert!(StatusLabels::from_keywords(&[Keyword::parse("#+TODO: BAD(x").unwrap()]).is_err());
+        assert_eq!(
+            StatusLabels::from_keywords(&[Keyword::parse("#+TITLE: TODO").unwrap()]).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn done_states() {
+        let labels = parse(&["#+TODO: TODO NEXT | DONE CANCELLED"]);
+
+        assert!(labels.is_done("DONE"));
+        assert!(labels.is_done("CANCELLED"));
+        assert!(!labels.is_done("NEXT"));
+        assert!(labels.is_active("NEXT"));
+        assert!(!labels.is_done("MISSING"));
+
+        let default = StatusLabels::default();
+        assert!(default.is_done("DONE"));
+        assert!(!default.is_done("STARTED"));
+    }
+
+    #[test]
+    fn cycle_states() {
+        let labels = parse(&[
+            "#+TODO: TODO NEXT | DONE",
+            "#+TYP_TODO: Fred Sara | FINISHED",
+        ]);
+
+        assert_eq!(labels.first(), Some("TODO"));
+        assert_eq!(labels.next("TODO"), Some("NEXT"));
+        assert_eq!(labels.next("NEXT"), Some("DONE"));
+        assert_eq!(labels.next("DONE"), None);
+        assert_eq!(labels.previous("DONE"), Some("NEXT"));
+        assert_eq!(labels.previous("TODO"), None);
+
+        assert_eq!(labels.next("Fred"), Some("FINISHED"));
+        assert_eq!(labels.next("Sara"), Some("FINISHED"));
+        assert_eq!(labels.previous("FINISHED"), Some("Fred"));
+    }
+
+    #[test]
+    fn labels_from_str() {
+        let labels: StatusLabels = "TODO,STARTED,DONE".parse().unwrap();
+        assert_eq!(labels, StatusLabels::default());
+
+        let labels: StatusLabels = "TODO(t) | DONE(d) WONTFIX".parse().unwrap();
+        assert!(labels.is_done("WONTFIX"));
+
+        assert!("TODO,NOT OK!".parse::<StatusLabels>().is_err());
     }
 }
@@ -0,0 +1,543 @@
+use std::{borrow::Cow, fmt, fmt::Display};
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::{error::Diagnostic, keyword::Keyword};
+
+mod formula;
+
+use formula::{split_formulas, Formula};
+
+/// the keyword of formula lines below a table
+pub const TBLFM: &str = "TBLFM";
+
+/// an org table, e.g.
+/// ```text
+/// | name | qty |
+/// |------+-----|
+/// | nuts |   3 |
+/// #+TBLFM: @>$2=vsum(@2..@-1)
+/// ```
+#[derive(Debug)]
+pub struct Table<'t> {
+    pub rows: Vec<Row<'t>>,
+    /// the lines the rows were parsed from
+    pub lines: Vec<&'t str>,
+    /// the `#+TBLFM:` lines after the table
+    pub formulas: Vec<Keyword<'t>>,
+    /// the line number of the first `#+TBLFM:` line, starting from 0
+    pub formulas_line: Option<usize>,
+    /// the indentation of the first row
+    pub indent: usize,
+    /// the whitespace before the first row as it was written
+    pub indent_text: &'t str,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Row<'t> {
+    /// a horizontal rule, `|---+---|`
+    Rule,
+    Cells(Vec<Cow<'t, str>>),
+}
+
+/// a column cookie like `<l>`, `<r10>` or `<15>`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColumnCookie {
+    pub alignment: Option<Alignment>,
+    pub width: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Alignment {
+    Left,
+    Right,
+    Center,
+}
+
+impl<'t> Row<'t> {
+    /// parse a table row, a line starting with `|`
+    pub fn parse(line: &'t str) -> Option<Row<'t>> {
+        let line = line.trim();
+        let rem = line.strip_prefix('|')?;
+        if rem.starts_with('-') {
+            return Some(Row::Rule);
+        }
+
+        let rem = rem.strip_suffix('|').unwrap_or(rem);
+        let cells = rem
+            .split('|')
+            .map(|cell| Cow::Borrowed(cell.trim()))
+            .collect();
+        Some(Row::Cells(cells))
+    }
+
+    pub fn cells(&self) -> &[Cow<'t, str>] {
+        match self {
+            Row::Rule => &[],
+            Row::Cells(cells) => cells,
+        }
+    }
+
+    pub fn is_rule(&self) -> bool {
+        *self == Row::Rule
+    }
+
+    /// check if the row only has column cookies and empty cells
+    pub fn is_cookie_row(&self) -> bool {
+        let cells = self.cells();
+        cells.iter().any(|cell| !cell.is_empty())
+            && cells
+                .iter()
+                .all(|cell| cell.is_empty() || ColumnCookie::parse(cell).is_some())
+    }
+}
+
+impl ColumnCookie {
+    pub fn parse(s: &str) -> Option<ColumnCookie> {
+        let inner = s.strip_prefix('<')?.strip_suffix('>')?;
+        let (alignment, width) = match inner.chars().next()? {
+            'l' => (Some(Alignment::Left), &inner[1..]),
+            'r' => (Some(Alignment::Right), &inner[1..]),
+            'c' => (Some(Alignment::Center), &inner[1..]),
+            _ => (None, inner),
+        };
+        let width = if width.is_empty() {
+            None
+        } else if width.chars().all(|ch| ch.is_ascii_digit()) {
+            Some(width.parse().ok()?)
+        } else {
+            return None;
+        };
+
+        if alignment.is_none() && width.is_none() {
+            return None;
+        }
+        Some(ColumnCookie { alignment, width })
+    }
+}
+
+impl<'t> Table<'t> {
+    pub fn new() -> Self {
+        Table {
+            rows: Vec::new(),
+            lines: Vec::new(),
+            formulas: Vec::new(),
+            formulas_line: None,
+            indent: 0,
+            indent_text: "",
+        }
+    }
+
+    /// add a row parsed from `line`.
+    /// the first line sets the indentation of the table.
+    pub fn push_line(&mut self, row: Row<'t>, line: &'t str) {
+        if self.rows.is_empty() {
+            self.indent_text = &line[..line.len() - line.trim_start().len()];
+            self.indent = self.indent_text.chars().count();
+        }
+        self.rows.push(row);
+        self.lines.push(line);
+    }
+
+    /// the cells of the table, each with the line of its row.
+    /// recalculated cells are not part of that line.
+    pub fn cells_by_line(&self) -> impl Iterator<Item = (&str, Option<&'t str>)> {
+        self.rows.iter().enumerate().flat_map(move |(i, row)| {
+            let line = self.lines.get(i).copied();
+            row.cells().iter().map(move |cell| (cell.as_ref(), line))
+        })
+    }
+
+    /// the rows with data, i.e. without rules and cookie rows
+    pub fn data_rows(&self) -> impl Iterator<Item = &[Cow<'t, str>]> {
+        self.rows
+            .iter()
+            .filter(|row| !row.is_rule() && !row.is_cookie_row())
+            .map(Row::cells)
+    }
+
+    /// the cell at `row` and `column` of the data rows, starting from 0
+    pub fn cell(&self, row: usize, column: usize) -> Option<&str> {
+        self.data_rows()
+            .nth(row)
+            .and_then(|cells| cells.get(column))
+            .map(|cell| cell.as_ref())
+    }
+
+    /// the number of columns in the widest row
+    pub fn column_count(&self) -> usize {
+        self.rows
+            .iter()
+            .map(|row| row.cells().len())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// the cells of `column` in the data rows.
+    /// missing cells in short rows are empty.
+    pub fn column(&self, column: usize) -> impl Iterator<Item = &str> {
+        self.data_rows()
+            .map(move |cells| cells.get(column).map(|cell| cell.as_ref()).unwrap_or(""))
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = Vec<&str>> {
+        (0..self.column_count()).map(move |column| self.column(column).collect())
+    }
+
+    /// the first cookie in `column`, e.g. `<r10>`
+    pub fn column_cookie(&self, column: usize) -> Option<ColumnCookie> {
+        self.rows
+            .iter()
+            .filter(|row| row.is_cookie_row())
+            .find_map(|row| {
+                row.cells()
+                    .get(column)
+                    .and_then(|cell| ColumnCookie::parse(cell))
+            })
+    }
+
+    /// the width and alignment of each column, like `org-table-align`.
+    /// a column is as wide as its widest cell, measured in display width.
+    /// a width cookie sets the minimum width of its column,
+    /// cells are never truncated because that would lose text.
+    /// columns without an alignment cookie are right aligned
+    /// if most of their cells are numbers.
+    pub fn layout(&self) -> Vec<(usize, Alignment)> {
+        (0..self.column_count())
+            .map(|column| {
+                let cookie = self.column_cookie(column);
+                let width = self
+                    .rows
+                    .iter()
+                    .filter_map(|row| row.cells().get(column))
+                    .map(|cell| cell.width())
+                    .chain(cookie.and_then(|cookie| cookie.width))
+                    .max()
+                    .unwrap_or(0)
+                    .max(1);
+
+                let alignment = cookie
+                    .and_then(|cookie| cookie.alignment)
+                    .unwrap_or_else(|| {
+                        let cells: Vec<&str> = self
+                            .column(column)
+                            .filter(|cell| !cell.is_empty())
+                            .collect();
+                        let numbers = cells.iter().filter(|cell| is_number(cell)).count();
+                        if !cells.is_empty() && numbers * 2 >= cells.len() {
+                            Alignment::Right
+                        } else {
+                            Alignment::Left
+                        }
+                    });
+                (width, alignment)
+            })
+            .collect()
+    }
+
+    /// evaluate the `#+TBLFM:` formulas and store the results in the cells.
+    /// like `org-table-recalculate`, column formulas are applied
+    /// before field formulas, so field formulas win.
+    /// formulas that can't be evaluated, e.g. elisp formulas,
+    /// are left out and reported as diagnostics.
+    pub fn recalculate(&mut self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let mut formulas = Vec::new();
+        for (i, keyword) in self.formulas.iter().enumerate() {
+            // the `#+TBLFM:` lines follow each other
+            let line = self.formulas_line.map(|line| line + i);
+            for text in split_formulas(keyword.value) {
+                match Formula::parse(text) {
+                    Ok(formula) => formulas.push((line, text, formula)),
+                    Err(message) => diagnostics.push(formula_diagnostic(line, text, message)),
+                }
+            }
+        }
+        formulas.sort_by_key(|(_, _, formula)| !formula.is_column_formula());
+
+        for (line, text, formula) in formulas {
+            if let Err(message) = formula.apply(self) {
+                diagnostics.push(formula_diagnostic(line, text, message));
+            }
+        }
+        diagnostics
+    }
+}
+
+fn formula_diagnostic(line: Option<usize>, formula: &str, message: String) -> Diagnostic {
+    Diagnostic::new(
+        line,
+        format!("table formula `{}` not applied: {}", formula, message),
+    )
+}
+
+/// check if a cell looks like a number, e.g. `-3`, `1.5e3`, `50%` or `1:30`
+fn is_number(cell: &str) -> bool {
+    let digits = cell.strip_prefix(['-', '+']).unwrap_or(cell);
+    digits.starts_with(|ch: char| ch.is_ascii_digit() || ch == '.')
+        && digits.chars().any(|ch| ch.is_ascii_digit())
+        && digits.chars().all(|ch| {
+            ch.is_ascii_digit() || matches!(ch, '.' | ',' | 'e' | 'E' | '%' | ':' | '-' | '+')
+        })
+}
+
+fn write_cell(
+    f: &mut fmt::Formatter<'_>,
+    cell: &str,
+    width: usize,
+    alignment: Alignment,
+) -> fmt::Result {
+    let padding = width.saturating_sub(cell.width());
+    let (left, right) = match alignment {
+        Alignment::Left => (0, padding),
+        Alignment::Right => (padding, 0),
+        Alignment::Center => (padding / 2, padding - padding / 2),
+    };
+    write!(f, " {}{}{} |", " ".repeat(left), cell, " ".repeat(right))
+}
+
+impl<'t> Default for Table<'t> {
+    fn default() -> Self {
+        Table::new()
+    }
+}
+
+/// formats the table aligned, like `org-table-align`
+impl<'t> Display for Table<'t> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let layout = self.layout();
+        let indent = if self.indent_text.chars().count() == self.indent {
+            Cow::Borrowed(self.indent_text)
+        } else {
+            Cow::Owned(" ".repeat(self.indent))
+        };
+        for row in &self.rows {
+            write!(f, "{}|", indent)?;
+            match row {
+                Row::Rule => {
+                    let dashes: Vec<String> = layout
+                        .iter()
+                        .map(|(width, _)| "-".repeat(width + 2))
+                        .collect();
+                    write!(f, "{}|", dashes.join("+"))?;
+                }
+                Row::Cells(cells) => {
+                    for (column, (width, alignment)) in layout.iter().enumerate() {
+                        let cell = cells.get(column).map(|cell| cell.as_ref()).unwrap_or("");
+                        write_cell(f, cell, *width, *alignment)?;
+                    }
+                }
+            }
+            writeln!(f)?;
+        }
+        for formula in &self.formulas {
+            // print the line as written unless the formulas were changed
+            match formula
+                .raw
+                .filter(|raw| Keyword::parse(raw) == Some(*formula))
+            {
+                Some(raw) => writeln!(f, "{}", raw)?,
+                None => writeln!(f, "{}#+{}: {}", indent, TBLFM, formula.value)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_rows() {
+        assert_eq!(Row::parse("|---+---|"), Some(Row::Rule));
+        assert_eq!(Row::parse("  |-"), Some(Row::Rule));
+        assert_eq!(
+            Row::parse("| a | b c |  |"),
+            Some(Row::Cells(vec!["a".into(), "b c".into(), "".into()]))
+        );
+        assert_eq!(
+            Row::parse("| unterminated"),
+            Some(Row::Cells(vec!["unterminated".into()]))
+        );
+        assert_eq!(Row::parse("not a | row"), None);
+
+        assert!(Row::parse("| <l> | <r10> | |").unwrap().is_cookie_row());
+        assert!(!Row::parse("| <l> | text |").unwrap().is_cookie_row());
+        assert!(!Row::parse("| | |").unwrap().is_cookie_row());
+    }
+
+    #[test]
+    fn parse_column_cookies() {
+        assert_eq!(
+            ColumnCookie::parse("<r10>"),
+            Some(ColumnCookie {
+                alignment: Some(Alignment::Right),
+                width: Some(10)
+            })
+        );
+        assert_eq!(
+            ColumnCookie::parse("<15>"),
+            Some(ColumnCookie {
+                alignment: None,
+                width: Some(15)
+            })
+        );
+        assert_eq!(ColumnCookie::parse("<c>").unwrap().width, None);
+        assert_eq!(ColumnCookie::parse("<>"), None);
+        assert_eq!(ColumnCookie::parse("<x1>"), None);
+    }
+
+    const UNALIGNED: &str = "  |name|qty|note|
+  |-
+  |||<c6>
+  |nuts|3
+  |bolts|12|x
+  | café ☕ | 1.5 | a longer note |
+";
+
+    const ALIGNED: &str = "  | name    | qty |     note      |
+  |---------+-----+---------------|
+  |         |     |     <c6>      |
+  | nuts    |   3 |               |
+  | bolts   |  12 |       x       |
+  | café ☕ | 1.5 | a longer note |
+";
+
+    #[test]
+    fn align_tables() {
+        let mut table = Table::new();
+        for line in UNALIGNED.lines() {
+            table.push_line(Row::parse(line).unwrap(), line);
+        }
+
+        assert_eq!(table.layout()[0], (7, Alignment::Left));
+        assert_eq!(table.layout()[1], (3, Alignment::Right));
+        assert_eq!(format!("{}", table), ALIGNED);
+    }
+
+    #[test]
+    fn keep_tab_indentation() {
+        let text = "\t| a | 1 |\n\t#+tblfm: $2=1\n";
+        let mut table = parse_table(text);
+        assert_eq!(table.indent_text, "\t");
+        assert_eq!(format!("{}", table), text);
+
+        table.formulas[0].value = "$2=2";
+        assert_eq!(format!("{}", table), "\t| a | 1 |\n\t#+TBLFM: $2=2\n");
+    }
+
+    #[test]
+    fn recognize_numbers() {
+        for number in ["3", "-1.5", "+2e10", "50%", "1:30", ".5", "1,000"] {
+            assert!(is_number(number), "{}", number);
+        }
+        for text in ["", "-", "abc", "3 apples", "e10", "<r5>"] {
+            assert!(!is_number(text), "{}", text);
+        }
+    }
+
+    fn parse_table(text: &str) -> Table<'_> {
+        let mut table = Table::new();
+        for line in text.lines() {
+            match Keyword::parse(line) {
+                Some(keyword) => table.formulas.push(keyword),
+                None => table.push_line(Row::parse(line).unwrap(), line),
+            }
+        }
+        table
+    }
+
+    const FORMULAS: &str = "| item  | qty | price | total |
+|-------+-----+-------+-------|
+| nuts  |   3 |   1.5 |       |
+| bolts |  12 |  0.25 |       |
+| nails |     |       |       |
+|-------+-----+-------+-------|
+| sum   |     |       |       |
+#+TBLFM: $4=$2*$3;%.2f::@>$2=vsum(@I..@II)::@>$4=vsum(@I$4..@II$4)
+#+TBLFM: @>$3=vmean(@2$3..@-1$3)
+";
+
+    #[test]
+    fn recalculate_formulas() {
+        let mut table = parse_table(FORMULAS);
+        assert!(table.recalculate().is_empty());
+        assert_eq!(
+            table.column(3).collect::<Vec<_>>()[1..],
+            ["4.50", "3.00", "0.00", "7.5"]
+        );
+        assert_eq!(table.cell(4, 1), Some("15"));
+        assert_eq!(table.cell(4, 2), Some("0.875"));
+
+        let mut table = parse_table(
+            "| 1 | 2 | |
+| 4 | 3 |
+#+TBLFM: @1$3=vmax($1..$2) - vmin(@1$1..@2$1)::@2$3=($<+$-1)^2/-$#::@3$1=1",
+        );
+        let diagnostics = table.recalculate();
+        assert_eq!(table.cell(0, 2), Some("1"));
+        assert_eq!(table.cell(1, 2), Some("-16.3333333333"));
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0]
+            .message
+            .contains("row 3 is outside of the table"));
+
+        let mut table = parse_table(
+            "| 1,000 | 2 | |
+#+TBLFM: $3=$1+$2
+#+TBLFM: $3=$2*2;%e",
+        );
+        table.formulas_line = Some(1);
+        let diagnostics = table.recalculate();
+        assert_eq!(table.cell(0, 2), Some("1002"));
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].line, Some(2));
+    }
+
+    #[test]
+    fn rule_references() {
+        let mut table = parse_table(
+            "| a | 1 | |
+|---+---+---|
+| b | 2 | |
+| c | 3 | |
+|---+---+---|
+| d | 4 | |
+#+TBLFM: @1$3=@II-1$2::@2$3=@I+2$2::@3$3=@II$2::@4$3=vsum(@I$2..@II$2)",
+        );
+        assert!(table.recalculate().is_empty());
+        assert_eq!(table.column(2).collect::<Vec<_>>(), ["3", "3", "3", "5"]);
+    }
+
+    #[test]
+    fn round_results() {
+        let mut table = parse_table("| 0.1 | 3 | |\n#+TBLFM: $3=$1*$2");
+        assert!(table.recalculate().is_empty());
+        assert_eq!(table.cell(0, 2), Some("0.3"));
+    }
+
+    #[test]
+    fn report_unsupported_formulas() {
+        let mut table = parse_table(
+            "| a | 1 | |
+#+TBLFM: $3='(+ $1 $2)::$3=$2*2;%e::$3=vcount($2)::$3=$1*2::@1$2=",
+        );
+        let messages: Vec<String> = table
+            .recalculate()
+            .into_iter()
+            .map(|diagnostic| diagnostic.message)
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "table formula `$3='(+ $1 $2)` not applied: elisp formulas are not supported",
+                "table formula `$3=$2*2;%e` not applied: unsupported format `%e`",
+                "table formula `$3=vcount($2)` not applied: unsupported function `vcount`",
+                "table formula `@1$2=` not applied: unexpected end of formula",
+                "table formula `$3=$1*2` not applied: `a` is not a number",
+            ]
+        );
+        assert_eq!(table.cell(0, 1), Some("1"));
+    }
+}
@@ -0,0 +1,621 @@
+//! evaluate the Calc-style subset of `#+TBLFM` formulas,
+//! e.g. `$4=$2*$3;%.2f` or `@>$2=vsum(@2..@-1)`
+
+use std::{borrow::Cow, iter::Peekable, str::Chars};
+
+use super::{Row, Table};
+
+/// a formula like `$3=$1*$2;%.2f`
+#[derive(Debug)]
+pub struct Formula {
+    target: Target,
+    expression: Expression,
+    format: Option<Format>,
+}
+
+#[derive(Debug)]
+enum Target {
+    /// `$3=...` applies to every row below the header
+    Column(ColumnRef),
+    /// `@2$3=...` applies to one field
+    Field(RowRef, ColumnRef),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum RowRef {
+    /// `@2`
+    Absolute(usize),
+    /// `@<`, `@<<`
+    First(usize),
+    /// `@>`, `@>>`
+    Last(usize),
+    /// `@-1`, `@+2`
+    Relative(isize),
+    /// `@I`, `@II+1`: the row before the nth rule, plus an offset
+    Rule(usize, isize),
+    /// `@#`, the current row number
+    Current,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ColumnRef {
+    /// `$2`
+    Absolute(usize),
+    /// `$<`
+    First,
+    /// `$>`
+    Last,
+    /// `$-1`, `$+1`
+    Relative(isize),
+    /// `$#`, the current column number
+    Current,
+}
+
+/// a reference to a field, `@2$3`, where either part can be left out
+#[derive(Debug, Clone, Copy)]
+struct FieldRef {
+    row: Option<RowRef>,
+    column: Option<ColumnRef>,
+}
+
+#[derive(Debug)]
+enum Expression {
+    Number(f64),
+    Field(FieldRef),
+    Range(FieldRef, FieldRef),
+    Negate(Box<Expression>),
+    Binary(char, Box<Expression>, Box<Expression>),
+    Call(Function, Box<Expression>),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Function {
+    Sum,
+    Mean,
+    Max,
+    Min,
+}
+
+/// a format specifier after `;`
+#[derive(Debug, Clone, Copy)]
+enum Format {
+    /// `%.2f` or `f2`
+    Decimals(usize),
+    /// `%d`
+    Integer,
+}
+
+enum Value {
+    Number(f64),
+    List(Vec<f64>),
+}
+
+/// the position of the field being computed
+#[derive(Clone, Copy)]
+struct Position {
+    /// the row number, starting from 1
+    row: usize,
+    /// the column number, starting from 1
+    column: usize,
+}
+
+/// split the value of a `#+TBLFM:` line into formulas
+pub fn split_formulas(value: &str) -> impl Iterator<Item = &str> {
+    value
+        .split("::")
+        .map(str::trim)
+        .filter(|formula| !formula.is_empty())
+}
+
+impl Formula {
+    pub fn parse(s: &str) -> Result<Formula, String> {
+        let (assignment, format) = match s.rsplit_once(';') {
+            Some((assignment, format)) => (assignment, Some(parse_format(format.trim())?)),
+            None => (s, None),
+        };
+        let (target, expression) = assignment
+            .split_once('=')
+            .ok_or_else(|| "missing `=`".to_string())?;
+        let expression = expression.trim();
+        if expression.starts_with('\'') {
+            return Err("elisp formulas are not supported".to_string());
+        }
+
+        let mut parser = Parser {
+            chars: target.trim().chars().peekable(),
+        };
+        let target = match parser.parse_field_ref()? {
+            Some(FieldRef {
+                row: None,
+                column: Some(column),
+            }) => Target::Column(column),
+            Some(FieldRef {
+                row: Some(row),
+                column: Some(column),
+            }) => Target::Field(row, column),
+            _ => return Err(format!("unsupported target `{}`", target.trim())),
+        };
+        parser.expect_end()?;
+
+        let mut parser = Parser {
+            chars: expression.chars().peekable(),
+        };
+        let expression = parser.parse_expression()?;
+        parser.expect_end()?;
+
+        Ok(Formula {
+            target,
+            expression,
+            format,
+        })
+    }
+
+    pub fn is_column_formula(&self) -> bool {
+        matches!(self.target, Target::Column(_))
+    }
+
+    /// compute the formula and store the results in `table`
+    pub fn apply(&self, table: &mut Table) -> Result<(), String> {
+        let rows = row_indexes(table);
+        let columns = table.column_count();
+        let context = Context {
+            table,
+            rows: &rows,
+            columns,
+        };
+
+        let fields: Vec<Position> = match self.target {
+            Target::Column(column) => {
+                let position = Position { row: 1, column: 1 };
+                let column = context.column(column, position)?;
+                context
+                    .body_rows()
+                    .map(|row| Position { row, column })
+                    .collect()
+            }
+            Target::Field(row, column) => {
+                let position = Position { row: 1, column: 1 };
+                vec![Position {
+                    row: context.row(row, position, false)?,
+                    column: context.column(column, position)?,
+                }]
+            }
+        };
+
+        let mut results = Vec::new();
+        for position in fields {
+            let value = match context.evaluate(&self.expression, position)? {
+                Value::Number(value) => value,
+                Value::List(_) => return Err("a range must be passed to a function".to_string()),
+            };
+            if !value.is_finite() {
+                return Err("the result is not a number".to_string());
+            }
+            results.push((position, format_number(value, self.format)));
+        }
+
+        for (position, value) in results {
+            let row = &mut table.rows[rows[position.row - 1]];
+            if let Row::Cells(cells) = row {
+                if cells.len() < position.column {
+                    cells.resize(position.column, Cow::Borrowed(""));
+                }
+                cells[position.column - 1] = Cow::Owned(value);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// the indexes of the rows that are not rules, so `@1` is `rows[0]`
+fn row_indexes(table: &Table) -> Vec<usize> {
+    table
+        .rows
+        .iter()
+        .enumerate()
+        .filter(|(_, row)| !row.is_rule())
+        .map(|(i, _)| i)
+        .collect()
+}
+
+struct Context<'a, 't> {
+    table: &'a Table<'t>,
+    /// the indexes of the rows that are not rules
+    rows: &'a [usize],
+    columns: usize,
+}
+
+impl<'a, 't> Context<'a, 't> {
+    /// the number of rows before the `n`th rule, starting from 1
+    fn rows_before_rule(&self, n: usize) -> Option<usize> {
+        let rule = self
+            .table
+            .rows
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| row.is_rule())
+            .nth(n.checked_sub(1)?)?
+            .0;
+        Some(self.rows.iter().take_while(|&&i| i < rule).count())
+    }
+
+    /// the rows column formulas apply to:
+    /// the rows below the first rule, if any, without cookie rows
+    fn body_rows(&self) -> impl Iterator<Item = usize> + '_ {
+        let first = self.rows_before_rule(1).unwrap_or(0) + 1;
+        (first..=self.rows.len())
+            .filter(move |&row| !self.table.rows[self.rows[row - 1]].is_cookie_row())
+    }
+
+    /// resolve a row reference to a row number.
+    /// a rule at the start of a range refers to the row after the rule.
+    fn row(&self, row: RowRef, position: Position, range_start: bool) -> Result<usize, String> {
+        let count = self.rows.len() as isize;
+        let row = match row {
+            RowRef::Absolute(row) => row as isize,
+            RowRef::First(n) => n as isize,
+            RowRef::Last(n) => count + 1 - n as isize,
+            RowRef::Relative(offset) => position.row as isize + offset,
+            RowRef::Rule(n, offset) => {
+                let before = self
+                    .rows_before_rule(n)
+                    .ok_or_else(|| format!("there is no rule number {}", n))?;
+                // offsets count rows from the rule, `@II-1` is the row above it
+                let before = before as isize;
+                match offset {
+                    0 if range_start => before + 1,
+                    0 => before,
+                    offset if offset > 0 => before + offset,
+                    offset => before + offset + 1,
+                }
+            }
+            RowRef::Current => position.row as isize,
+        };
+        if row < 1 || row > count {
+            return Err(format!("row {} is outside of the table", row));
+        }
+        Ok(row as usize)
+    }
+
+    fn column(&self, column: ColumnRef, position: Position) -> Result<usize, String> {
+        let column = match column {
+            ColumnRef::Absolute(column) => column as isize,
+            ColumnRef::First => 1,
+            ColumnRef::Last => self.columns as isize,
+            ColumnRef::Relative(offset) => position.column as isize + offset,
+            ColumnRef::Current => position.column as isize,
+        };
+        if column < 1 || column > self.columns as isize {
+            return Err(format!("column {} is outside of the table", column));
+        }
+        Ok(column as usize)
+    }
+
+    fn field(&self, row: usize, column: usize) -> &str {
+        self.table.rows[self.rows[row - 1]]
+            .cells()
+            .get(column - 1)
+            .map(|cell| cell.as_ref())
+            .unwrap_or("")
+    }
+
+    fn resolve(
+        &self,
+        field: FieldRef,
+        position: Position,
+        range_start: bool,
+    ) -> Result<Position, String> {
+        Ok(Position {
+            row: match field.row {
+                Some(row) => self.row(row, position, range_start)?,
+                None => position.row,
+            },
+            column: match field.column {
+                Some(column) => self.column(column, position)?,
+                None => position.column,
+            },
+        })
+    }
+
+    fn evaluate(&self, expression: &Expression, position: Position) -> Result<Value, String> {
+        let number = |expression: &Expression| match self.evaluate(expression, position)? {
+            Value::Number(value) => Ok(value),
+            Value::List(_) => Err("a range must be passed to a function".to_string()),
+        };
+
+        let value = match expression {
+            Expression::Number(value) => *value,
+            Expression::Field(FieldRef {
+                row: Some(RowRef::Current),
+                column: None,
+            }) => position.row as f64,
+            Expression::Field(FieldRef {
+                row: None,
+                column: Some(ColumnRef::Current),
+            }) => position.column as f64,
+            Expression::Field(field) => {
+                let field = self.resolve(*field, position, false)?;
+                parse_field(self.field(field.row, field.column))?.unwrap_or(0.0)
+            }
+            Expression::Range(start, end) => {
+                let start = self.resolve(*start, position, true)?;
+                let end = self.resolve(*end, position, false)?;
+                let mut values = Vec::new();
+                for row in start.row.min(end.row)..=start.row.max(end.row) {
+                    for column in start.column.min(end.column)..=start.column.max(end.column) {
+                        // empty fields are left out of ranges
+                        values.extend(parse_field(self.field(row, column))?);
+                    }
+                }
+                return Ok(Value::List(values));
+            }
+            Expression::Negate(expression) => -number(expression)?,
+            Expression::Binary(op, left, right) => {
+                let (left, right) = (number(left)?, number(right)?);
+                match op {
+                    '+' => left + right,
+                    '-' => left - right,
+                    '*' => left * right,
+                    '/' => left / right,
+                    _ => left.powf(right),
+                }
+            }
+            Expression::Call(function, argument) => {
+                let values = match self.evaluate(argument, position)? {
+                    Value::Number(value) => vec![value],
+                    Value::List(values) => values,
+                };
+                function.call(&values)
+            }
+        };
+        Ok(Value::Number(value))
+    }
+}
+
+/// parse a field as a number.
+/// empty fields are `None`.
+fn parse_field(field: &str) -> Result<Option<f64>, String> {
+    if field.is_empty() {
+        return Ok(None);
+    }
+    // thousands separators, like `1,000`
+    field
+        .replace(',', "")
+        .parse()
+        .map(Some)
+        .map_err(|_| format!("`{}` is not a number", field))
+}
+
+impl Function {
+    fn call(self, values: &[f64]) -> f64 {
+        match self {
+            Function::Sum => values.iter().sum(),
+            Function::Mean if values.is_empty() => 0.0,
+            Function::Mean => values.iter().sum::<f64>() / values.len() as f64,
+            Function::Max => values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            Function::Min => values.iter().copied().fold(f64::INFINITY, f64::min),
+        }
+    }
+}
+
+fn parse_format(format: &str) -> Result<Format, String> {
+    let decimals = format
+        .strip_prefix("%.")
+        .and_then(|rem| rem.strip_suffix('f'))
+        .or_else(|| format.strip_prefix('f'));
+    match (format, decimals) {
+        ("%d", _) => Ok(Format::Integer),
+        ("%f", _) => Ok(Format::Decimals(6)),
+        (_, Some(decimals)) => decimals
+            .parse()
+            .map(Format::Decimals)
+            .map_err(|_| format!("unsupported format `{}`", format)),
+        _ => Err(format!("unsupported format `{}`", format)),
+    }
+}
+
+fn format_number(value: f64, format: Option<Format>) -> String {
+    match format {
+        Some(Format::Decimals(decimals)) => format!("{:.*}", decimals, value),
+        Some(Format::Integer) => format!("{}", value.round()),
+        None => format!("{}", round_significant(value)),
+    }
+}
+
+/// round to the 12 significant digits Calc computes with,
+/// so `0.1 * 3` is `0.3`
+fn round_significant(value: f64) -> f64 {
+    format!("{:.11e}", value).parse().unwrap_or(value)
+}
+
+struct Parser<'s> {
+    chars: Peekable<Chars<'s>>,
+}
+
+impl<'s> Parser<'s> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|ch| ch.is_whitespace()).is_some() {}
+    }
+
+    fn expect_end(&mut self) -> Result<(), String> {
+        self.skip_whitespace();
+        match self.chars.next() {
+            Some(ch) => Err(format!("unexpected `{}`", ch)),
+            None => Ok(()),
+        }
+    }
+
+    fn parse_expression(&mut self) -> Result<Expression, String> {
+        let mut expression = self.parse_term()?;
+        loop {
+            self.skip_whitespace();
+            match self.chars.next_if(|ch| *ch == '+' || *ch == '-') {
+                Some(op) => {
+                    let right = self.parse_term()?;
+                    expression = Expression::Binary(op, Box::new(expression), Box::new(right));
+                }
+                None => return Ok(expression),
+            }
+        }
+    }
+
+    fn parse_term(&mut self) -> Result<Expression, String> {
+        let mut expression = self.parse_factor()?;
+        loop {
+            self.skip_whitespace();
+            match self.chars.next_if(|ch| *ch == '*' || *ch == '/') {
+                Some(op) => {
+                    let right = self.parse_factor()?;
+                    expression = Expression::Binary(op, Box::new(expression), Box::new(right));
+                }
+                None => return Ok(expression),
+            }
+        }
+    }
+
+    fn parse_factor(&mut self) -> Result<Expression, String> {
+        self.skip_whitespace();
+        if self.chars.next_if_eq(&'-').is_some() {
+            return Ok(Expression::Negate(Box::new(self.parse_factor()?)));
+        }
+
+        let base = self.parse_primary()?;
+        self.skip_whitespace();
+        if self.chars.next_if_eq(&'^').is_some() {
+            let exponent = self.parse_factor()?;
+            return Ok(Expression::Binary('^', Box::new(base), Box::new(exponent)));
+        }
+        Ok(base)
+    }
+
+    fn parse_primary(&mut self) -> Result<Expression, String> {
+        self.skip_whitespace();
+        match self.chars.peek() {
+            Some('(') => {
+                self.chars.next();
+                let expression = self.parse_expression()?;
+                self.skip_whitespace();
+                self.chars
+                    .next_if_eq(&')')
+                    .ok_or_else(|| "missing `)`".to_string())?;
+                Ok(expression)
+            }
+            Some('@') | Some('$') => {
+                let start = self.parse_field_ref()?.ok_or("expected a field")?;
+                if self.chars.next_if_eq(&'.').is_some() {
+                    self.chars
+                        .next_if_eq(&'.')
+                        .ok_or_else(|| "expected `..`".to_string())?;
+                    let end = self.parse_field_ref()?.ok_or("expected a field")?;
+                    return Ok(Expression::Range(start, end));
+                }
+                Ok(Expression::Field(start))
+            }
+            Some(ch) if ch.is_ascii_digit() || *ch == '.' => {
+                let number = self.take_while(|ch| ch.is_ascii_digit() || ch == '.');
+                number
+                    .parse()
+                    .map(Expression::Number)
+                    .map_err(|_| format!("`{}` is not a number", number))
+            }
+            Some(ch) if ch.is_ascii_alphabetic() => {
+                let name = self.take_while(|ch| ch.is_ascii_alphanumeric());
+                let function = match name.as_str() {
+                    "vsum" => Function::Sum,
+                    "vmean" => Function::Mean,
+                    "vmax" => Function::Max,
+                    "vmin" => Function::Min,
+                    _ => return Err(format!("unsupported function `{}`", name)),
+                };
+                self.chars
+                    .next_if_eq(&'(')
+                    .ok_or_else(|| format!("expected `(` after `{}`", name))?;
+                let argument = self.parse_expression()?;
+                self.skip_whitespace();
+                self.chars
+                    .next_if_eq(&')')
+                    .ok_or_else(|| "missing `)`".to_string())?;
+                Ok(Expression::Call(function, Box::new(argument)))
+            }
+            Some(ch) => Err(format!("unexpected `{}`", ch)),
+            None => Err("unexpected end of formula".to_string()),
+        }
+    }
+
+    /// parse `@row$column`, `@row` or `$column`
+    fn parse_field_ref(&mut self) -> Result<Option<FieldRef>, String> {
+        let row = match self.chars.next_if_eq(&'@') {
+            Some(_) => Some(self.parse_row_ref()?),
+            None => None,
+        };
+        let column = match self.chars.next_if_eq(&'$') {
+            Some(_) => Some(self.parse_column_ref()?),
+            None => None,
+        };
+        if row.is_none() && column.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(FieldRef { row, column }))
+    }
+
+    fn parse_row_ref(&mut self) -> Result<RowRef, String> {
+        match self.chars.peek() {
+            Some('<') => Ok(RowRef::First(self.take_while(|ch| ch == '<').len())),
+            Some('>') => Ok(RowRef::Last(self.take_while(|ch| ch == '>').len())),
+            Some('#') => {
+                self.chars.next();
+                Ok(RowRef::Current)
+            }
+            Some('I') => {
+                let rule = self.take_while(|ch| ch == 'I').len();
+                let offset = match self.chars.peek() {
+                    Some('+') | Some('-') => self.parse_offset()?,
+                    _ => 0,
+                };
+                Ok(RowRef::Rule(rule, offset))
+            }
+            Some('+') | Some('-') => Ok(RowRef::Relative(self.parse_offset()?)),
+            _ => Ok(RowRef::Absolute(self.parse_index()?)),
+        }
+    }
+
+    fn parse_column_ref(&mut self) -> Result<ColumnRef, String> {
+        match self.chars.peek() {
+            Some('<') => {
+                self.chars.next();
+                Ok(ColumnRef::First)
+            }
+            Some('>') => {
+                self.chars.next();
+                Ok(ColumnRef::Last)
+            }
+            Some('#') => {
+                self.chars.next();
+                Ok(ColumnRef::Current)
+            }
+            Some('+') | Some('-') => Ok(ColumnRef::Relative(self.parse_offset()?)),
+            _ => Ok(ColumnRef::Absolute(self.parse_index()?)),
+        }
+    }
+
+    fn parse_offset(&mut self) -> Result<isize, String> {
+        let negative = self.chars.next() == Some('-');
+        let offset = self.parse_index()? as isize;
+        Ok(if negative { -offset } else { offset })
+    }
+
+    fn parse_index(&mut self) -> Result<usize, String> {
+        let digits = self.take_while(|ch| ch.is_ascii_digit());
+        digits
+            .parse()
+            .map_err(|_| "expected a row or column number".to_string())
+    }
+
+    fn take_while<P: Fn(char) -> bool>(&mut self, predicate: P) -> String {
+        let mut taken = String::new();
+        while let Some(ch) = self.chars.next_if(|ch| predicate(*ch)) {
+            taken.push(ch);
+        }
+        taken
+    }
+}
@@ -0,0 +1,155 @@
+use std::{fmt, fmt::Display};
+
+/// the group tags declared with `#+TAGS:` lines, e.g.
+/// `#+TAGS: [ Project : ProjA ProjB ]` or `#+TAGS: { Context : @home @work }`.
+/// a group tag matches itself and all of its members.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TagGroups<'t> {
+    groups: Vec<TagGroup<'t>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagGroup<'t> {
+    pub name: &'t str,
+    pub members: Vec<&'t str>,
+    /// groups in braces are mutually exclusive
+    pub exclusive: bool,
+}
+
+impl<'t> TagGroups<'t> {
+    /// parse the groups in the value of a `#+TAGS:` keyword.
+    /// tags outside of groups and groups without a name are ignored.
+    pub fn parse(value: &'t str) -> TagGroups<'t> {
+        let mut groups = TagGroups::default();
+        groups.extend(value);
+        groups
+    }
+
+    /// add the groups in the value of another `#+TAGS:` keyword
+    pub fn extend(&mut self, value: &'t str) {
+        // the tokens of the group being parsed and if it is exclusive
+        let mut group: Option<(Vec<&'t str>, bool)> = None;
+        for token in value.split_whitespace() {
+            match (token, group.as_mut()) {
+                ("[", None) => group = Some((Vec::new(), false)),
+                ("{", None) => group = Some((Vec::new(), true)),
+                ("]", Some(_)) | ("}", Some(_)) => {
+                    let (tokens, exclusive) = group.take().unwrap();
+                    if let [name, ":", members @ ..] = tokens.as_slice() {
+                        self.groups.push(TagGroup {
+                            name,
+                            members: members.to_vec(),
+                            exclusive,
+                        });
+                    }
+                }
+                (token, Some((tokens, _))) => tokens.push(strip_fast_access(token)),
+                _ => {}
+            }
+        }
+    }
+
+    pub fn groups(&self) -> &[TagGroup<'t>] {
+        &self.groups
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// `tag` and all the tags it stands for, including the members of nested groups
+    pub fn expand<'a>(&self, tag: &'a str) -> Vec<&'a str>
+    where
+        't: 'a,
+    {
+        let mut tags = vec![tag];
+        let mut i = 0;
+        while i < tags.len() {
+            let name = tags[i];
+            for group in self.groups.iter().filter(|group| group.name == name) {
+                for member in &group.members {
+                    if !tags.contains(member) {
+                        tags.push(member);
+                    }
+                }
+            }
+            i += 1;
+        }
+        tags
+    }
+
+    /// check if a search for `search` matches `tag`,
+    /// either directly or because `search` is a group containing `tag`
+    pub fn matches(&self, search: &str, tag: &str) -> bool {
+        self.expand(search).contains(&tag)
+    }
+}
+
+/// strip a fast access key, e.g. the `(w)` in `@work(w)`
+fn strip_fast_access(tag: &str) -> &str {
+    match tag.find('(') {
+        Some(i) if i > 0 && tag.ends_with(')') => &tag[..i],
+        _ => tag,
+    }
+}
+
+/// parse the tags in the value of a `#+FILETAGS:` keyword,
+/// e.g. `:tag1:tag2:` or `tag1 tag2`
+pub fn parse_tag_list(value: &str) -> impl Iterator<Item = &str> {
+    value
+        .split(|ch: char| ch == ':' || ch.is_whitespace())
+        .filter(|tag| !tag.is_empty())
+}
+
+/// formats like the value of a `#+TAGS:` keyword
+impl<'t> Display for TagGroup<'t> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (open, close) = if self.exclusive {
+            ("{", "}")
+        } else {
+            ("[", "]")
+        };
+        write!(f, "{} {} :", open, self.name)?;
+        for member in &self.members {
+            write!(f, " {}", member)?;
+        }
+        write!(f, " {}", close)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_tag_groups() {
+        let mut groups = TagGroups::parse("[ Project : ProjA ProjB ] laptop(l)");
+        groups.extend("{ Context : @home(h) @work(w) } { a b }");
+        groups.extend("[ ProjA : ProjA1 ProjA2 ]");
+
+        assert_eq!(groups.groups().len(), 3);
+        assert!(groups.groups()[1].exclusive);
+        assert_eq!(groups.groups()[1].members, vec!["@home", "@work"]);
+        assert_eq!(
+            format!("{}", groups.groups()[0]),
+            "[ Project : ProjA ProjB ]"
+        );
+
+        assert_eq!(
+            groups.expand("Project"),
+            vec!["Project", "ProjA", "ProjB", "ProjA1", "ProjA2"]
+        );
+        assert!(groups.matches("Project", "ProjA2"));
+        assert!(groups.matches("laptop", "laptop"));
+        assert!(!groups.matches("ProjA", "ProjB"));
+    }
+
+    #[test]
+    fn parse_file_tags() {
+        let tags: Vec<_> = parse_tag_list(":work:urgent:").collect();
+        assert_eq!(tags, vec!["work", "urgent"]);
+
+        let tags: Vec<_> = parse_tag_list("work urgent").collect();
+        assert_eq!(tags, vec!["work", "urgent"]);
+    }
+}
@@ -0,0 +1,709 @@
+use std::{fmt, fmt::Display, ops::Range};
+
+const DAY_NAMES: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// a calendar date in the proleptic Gregorian calendar
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    year: i32,
+    month: u32,
+    day: u32,
+}
+
+/// a time of day with minute precision
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Time {
+    hour: u32,
+    minute: u32,
+}
+
+/// an org timestamp, e.g. `<2020-02-28 Fri 10:00-11:30 +1w -3d>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timestamp {
+    /// `<...>` stamps are active, `[...]` stamps are inactive
+    pub active: bool,
+    pub date: Date,
+    pub time: Option<Time>,
+    pub end: Option<RangeEnd>,
+    pub repeater: Option<Repeater>,
+    pub delay: Option<Delay>,
+}
+
+/// the end of a timestamp range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangeEnd {
+    /// a time range on the same day: `<2020-02-28 Fri 10:00-11:30>`
+    Time(Time),
+    /// a date range: `<2020-02-28 Fri>--<2020-03-01 Sun>`
+    Date(Date, Option<Time>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Unit {
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interval {
+    pub value: u32,
+    pub unit: Unit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepeaterKind {
+    /// `+1w`: shift the date by the interval once
+    Cumulate,
+    /// `++1w`: shift the date by the interval until it is in the future
+    CatchUp,
+    /// `.+1w`: shift the date to the interval after today
+    Restart,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Repeater {
+    pub kind: RepeaterKind,
+    pub interval: Interval,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DelayKind {
+    /// `-3d`: warn before every occurrence
+    All,
+    /// `--3d`: warn before the first occurrence only
+    First,
+}
+
+/// a deadline warning period or a scheduled delay
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Delay {
+    pub kind: DelayKind,
+    pub interval: Interval,
+}
+
+impl Date {
+    pub fn new(year: i32, month: u32, day: u32) -> Option<Date> {
+        if month == 0 || month > 12 || day == 0 || day > days_in_month(year, month) {
+            None
+        } else {
+            Some(Date { year, month, day })
+        }
+    }
+
+    /// parse a `YYYY-MM-DD` date
+    pub fn parse(s: &str) -> Option<Date> {
+        let mut parts = s.splitn(3, '-');
+        let year = parse_digits(parts.next()?, 4)?;
+        let month = parse_digits(parts.next()?, 2)?;
+        let day = parse_digits(parts.next()?, 2)?;
+        Date::new(year as i32, month, day)
+    }
+
+    pub fn year(&self) -> i32 {
+        self.year
+    }
+
+    pub fn month(&self) -> u32 {
+        self.month
+    }
+
+    pub fn day(&self) -> u32 {
+        self.day
+    }
+
+    /// the number of days since 1970-01-01
+    pub fn days(&self) -> i64 {
+        // http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+        let year = i64::from(self.year) - if self.month <= 2 { 1 } else { 0 };
+        let era = year.div_euclid(400);
+        let year_of_era = year.rem_euclid(400);
+        let month = i64::from(self.month);
+        let day_of_year =
+            (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + i64::from(self.day) - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146_097 + day_of_era - 719_468
+    }
+
+    /// the date `days` days since 1970-01-01
+    pub fn from_days(days: i64) -> Date {
+        // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let mp = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = (year_of_era + era * 400 + if month <= 2 { 1 } else { 0 }) as i32;
+        Date { year, month, day }
+    }
+
+    /// the day of the week, starting from 0 for Monday
+    pub fn weekday(&self) -> u32 {
+        // 1970-01-01 was a Thursday
+        (self.days() + 3).rem_euclid(7) as u32
+    }
+
+    pub fn day_name(&self) -> &'static str {
+        DAY_NAMES[self.weekday() as usize]
+    }
+
+    pub fn add_days(&self, days: i64) -> Date {
+        Date::from_days(self.days() + days)
+    }
+
+    /// add months, clamping the day to the end of the month
+    pub fn add_months(&self, months: i64) -> Date {
+        let month_index = i64::from(self.year) * 12 + i64::from(self.month) - 1 + months;
+        let year = month_index.div_euclid(12) as i32;
+        let month = month_index.rem_euclid(12) as u32 + 1;
+        let day = self.day.min(days_in_month(year, month));
+        Date { year, month, day }
+    }
+
+    /// add an interval.
+    /// hours are ignored since dates have no time.
+    pub fn add_interval(&self, interval: Interval, times: i64) -> Date {
+        let value = i64::from(interval.value) * times;
+        match interval.unit {
+            Unit::Hour => *self,
+            Unit::Day => self.add_days(value),
+            Unit::Week => self.add_days(value * 7),
+            Unit::Month => self.add_months(value),
+            Unit::Year => self.add_months(value * 12),
+        }
+    }
+}
+
+impl Time {
+    /// a time of day. `24:00` is allowed for the end of a day
+    pub fn new(hour: u32, minute: u32) -> Option<Time> {
+        if hour > 24 || minute > 59 || (hour == 24 && minute > 0) {
+            None
+        } else {
+            Some(Time { hour, minute })
+        }
+    }
+
+    /// parse an `H:MM` or `HH:MM` time
+    pub fn parse(s: &str) -> Option<Time> {
+        let (hour, minute) = s.split_at(s.find(':')?);
+        let hour = match hour.len() {
+            1 | 2 => parse_digits(hour, hour.len())?,
+            _ => return None,
+        };
+        Time::new(hour, parse_digits(&minute[1..], 2)?)
+    }
+
+    pub fn hour(&self) -> u32 {
+        self.hour
+    }
+
+    pub fn minute(&self) -> u32 {
+        self.minute
+    }
+
+    /// minutes since midnight
+    pub fn minutes(&self) -> u32 {
+        self.hour * 60 + self.minute
+    }
+}
+
+impl Interval {
+    /// parse an interval like `3d` or `1w`
+    pub fn parse(s: &str) -> Option<Interval> {
+        let unit = match s.chars().last()? {
+            'h' => Unit::Hour,
+            'd' => Unit::Day,
+            'w' => Unit::Week,
+            'm' => Unit::Month,
+            'y' => Unit::Year,
+            _ => return None,
+        };
+        let digits = &s[..s.len() - 1];
+        let value = parse_digits(digits, digits.len())?;
+        Some(Interval { value, unit })
+    }
+
+    /// the length of the interval in days,
+    /// approximating months and years
+    pub fn days(&self) -> i64 {
+        let value = i64::from(self.value);
+        match self.unit {
+            Unit::Hour => value / 24,
+            Unit::Day => value,
+            Unit::Week => value * 7,
+            Unit::Month => value * 30,
+            Unit::Year => value * 365,
+        }
+    }
+}
+
+impl Repeater {
+    pub fn parse(s: &str) -> Option<Repeater> {
+        let (kind, rem) = if let Some(rem) = s.strip_prefix("++") {
+            (RepeaterKind::CatchUp, rem)
+        } else if let Some(rem) = s.strip_prefix(".+") {
+            (RepeaterKind::Restart, rem)
+        } else {
+            (RepeaterKind::Cumulate, s.strip_prefix('+')?)
+        };
+        Interval::parse(rem).map(|interval| Repeater { kind, interval })
+    }
+}
+
+impl Delay {
+    pub fn parse(s: &str) -> Option<Delay> {
+        let (kind, rem) = if let Some(rem) = s.strip_prefix("--") {
+            (DelayKind::First, rem)
+        } else {
+            (DelayKind::All, s.strip_prefix('-')?)
+        };
+        Interval::parse(rem).map(|interval| Delay { kind, interval })
+    }
+}
+
+impl Timestamp {
+    /// create an active timestamp on a date
+    pub fn new(date: Date) -> Timestamp {
+        Timestamp {
+            active: true,
+            date,
+            time: None,
+            end: None,
+            repeater: None,
+            delay: None,
+        }
+    }
+
+    /// parse a string containing exactly one timestamp
+    pub fn parse(s: &str) -> Option<Timestamp> {
+        match Timestamp::parse_prefix(s.trim()) {
+            Some((timestamp, "")) => Some(timestamp),
+            _ => None,
+        }
+    }
+
+    /// parse a timestamp from the front of `text`
+    /// and return it with the remaining text
+    pub fn parse_prefix(text: &str) -> Option<(Timestamp, &str)> {
+        let (mut timestamp, rem) = parse_single(text)?;
+
+        // `<a>--<b>` date ranges
+        if let Some((end, rem)) = rem
+            .strip_prefix("--")
+            .and_then(parse_single)
+            .filter(|(end, _)| end.active == timestamp.active && end.end.is_none())
+        {
+            if timestamp.end.is_none() {
+                timestamp.end = Some(RangeEnd::Date(end.date, end.time));
+                return Some((timestamp, rem));
+            }
+        }
+
+        Some((timestamp, rem))
+    }
+
+    /// the date and time the timestamp ends,
+    /// or the start if it is not a range
+    pub fn end_date(&self) -> (Date, Option<Time>) {
+        match self.end {
+            Some(RangeEnd::Time(time)) => (self.date, Some(time)),
+            Some(RangeEnd::Date(date, time)) => (date, time),
+            None => (self.date, self.time),
+        }
+    }
+
+    /// the timestamp with the date moved by `days`
+    pub fn shift_days(&self, days: i64) -> Timestamp {
+        let end = match self.end {
+            Some(RangeEnd::Date(date, time)) => Some(RangeEnd::Date(date.add_days(days), time)),
+            end => end,
+        };
+        Timestamp {
+            date: self.date.add_days(days),
+            end,
+            ..*self
+        }
+    }
+
+    /// iterate over the dates that this timestamp
+    /// occurs on between `from` and `to` inclusive,
+    /// expanding any repeater
+    pub fn occurrences(&self, from: Date, to: Date) -> impl Iterator<Item = Date> {
+        let date = self.date;
+        let interval = self.repeater.map(|repeater| repeater.interval);
+
+        // skip ahead close to `from` before stepping,
+        // overestimating the length of the interval
+        let mut n = match interval {
+            Some(interval) if date < from && interval.days() > 0 => {
+                let max_days = interval.days() + interval.days() / 28 + 1;
+                ((from.days() - date.days()) / max_days).max(0)
+            }
+            _ => 0,
+        };
+
+        let mut last: Option<Date> = None;
+        std::iter::from_fn(move || loop {
+            let current = match interval {
+                Some(interval) => date.add_interval(interval, n),
+                None if n == 0 => date,
+                None => return None,
+            };
+            n += 1;
+
+            if current > to || last.is_some_and(|last| current <= last) {
+                return None;
+            }
+            last = Some(current);
+            if current >= from {
+                return Some(current);
+            }
+        })
+    }
+}
+
+/// parse a single `<...>` or `[...]` stamp
+fn parse_single(text: &str) -> Option<(Timestamp, &str)> {
+    let (active, close) = match text.chars().next()? {
+        '<' => (true, '>'),
+        '[' => (false, ']'),
+        _ => return None,
+    };
+    let end = text.find(close)?;
+    let inner = &text[1..end];
+    let rem = &text[end + 1..];
+
+    let mut tokens = inner.split_whitespace();
+    let date = Date::parse(tokens.next()?)?;
+    let mut timestamp = Timestamp {
+        active,
+        ..Timestamp::new(date)
+    };
+
+    for token in tokens {
+        if token.chars().all(char::is_alphabetic) {
+            // day name
+            if timestamp.time.is_some() || timestamp.repeater.is_some() {
+                return None;
+            }
+        } else if token.starts_with(|ch: char| ch.is_ascii_digit()) {
+            if timestamp.time.is_some() {
+                return None;
+            }
+            let (start, end) = match token.find('-') {
+                Some(i) => (&token[..i], Some(Time::parse(&token[i + 1..])?)),
+                None => (token, None),
+            };
+            timestamp.time = Some(Time::parse(start)?);
+            timestamp.end = end.map(RangeEnd::Time);
+        } else if let Some(repeater) = Repeater::parse(token) {
+            if timestamp.repeater.replace(repeater).is_some() {
+                return None;
+            }
+        } else if let Some(delay) = Delay::parse(token) {
+            if timestamp.delay.replace(delay).is_some() {
+                return None;
+            }
+        } else {
+            return None;
+        }
+    }
+
+    Some((timestamp, rem))
+}
+
+/// iterate over all timestamps in `text`
+/// along with their byte ranges
+pub fn find_timestamps(text: &str) -> Timestamps<'_> {
+    Timestamps { text, offset: 0 }
+}
+
+pub struct Timestamps<'t> {
+    text: &'t str,
+    offset: usize,
+}
+
+impl<'t> Iterator for Timestamps<'t> {
+    type Item = (Range<usize>, Timestamp);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(i) = self.text[self.offset..].find(['<', '[']) {
+            let start = self.offset + i;
+            match Timestamp::parse_prefix(&self.text[start..]) {
+                Some((timestamp, rem)) => {
+                    let end = self.text.len() - rem.len();
+                    self.offset = end;
+                    return Some((start..end, timestamp));
+                }
+                None => self.offset = start + 1,
+            }
+        }
+        self.offset = self.text.len();
+        None
+    }
+}
+
+fn parse_digits(s: &str, len: usize) -> Option<u32> {
+    if s.len() == len && s.chars().all(|ch| ch.is_ascii_digit()) {
+        s.parse().ok()
+    } else {
+        None
+    }
+}
+
+fn is_leap_year(year: i32) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {}",
+            self.year,
+            self.month,
+            self.day,
+            self.day_name()
+        )
+    }
+}
+
+impl Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.hour, self.minute)
+    }
+}
+
+impl Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unit = match self.unit {
+            Unit::Hour => 'h',
+            Unit::Day => 'd',
+            Unit::Week => 'w',
+            Unit::Month => 'm',
+            Unit::Year => 'y',
+        };
+        write!(f, "{}{}", self.value, unit)
+    }
+}
+
+impl Display for Repeater {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mark = match self.kind {
+            RepeaterKind::Cumulate => "+",
+            RepeaterKind::CatchUp => "++",
+            RepeaterKind::Restart => ".+",
+        };
+        write!(f, "{}{}", mark, self.interval)
+    }
+}
+
+impl Display for Delay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mark = match self.kind {
+            DelayKind::All => "-",
+            DelayKind::First => "--",
+        };
+        write!(f, "{}{}", mark, self.interval)
+    }
+}
+
+impl Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (open, close) = if self.active { ('<', '>') } else { ('[', ']') };
+        let write_stamp = |f: &mut fmt::Formatter<'_>, date: Date, time: Option<Time>| {
+            write!(f, "{}{}", open, date)?;
+            if let Some(time) = time {
+                write!(f, " {}", time)?;
+            }
+            if let Some(RangeEnd::Time(end)) = self.end {
+                write!(f, "-{}", end)?;
+            }
+            if let Some(repeater) = self.repeater {
+                write!(f, " {}", repeater)?;
+            }
+            if let Some(delay) = self.delay {
+                write!(f, " {}", delay)?;
+            }
+            write!(f, "{}", close)
+        };
+
+        write_stamp(f, self.date, self.time)?;
+        if let Some(RangeEnd::Date(date, time)) = self.end {
+            write!(f, "--")?;
+            write_stamp(f, date, time)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> Date {
+        Date::new(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn date_arithmetic() {
+        assert_eq!(date(1970, 1, 1).days(), 0);
+        assert_eq!(Date::from_days(0), date(1970, 1, 1));
+        assert_eq!(date(2026, 10, 18).day_name(), "Sun");
+        assert_eq!(date(2000, 2, 28).add_days(1), date(2000, 2, 29));
+        assert_eq!(date(2020, 12, 31).add_days(1), date(2021, 1, 1));
+        assert_eq!(date(2020, 1, 31).add_months(1), date(2020, 2, 29));
+        assert_eq!(date(2020, 11, 15).add_months(-13), date(2019, 10, 15));
+        assert_eq!(Date::from_days(date(1899, 3, 5).days()), date(1899, 3, 5));
+        assert_eq!(Date::new(2021, 2, 29), None);
+    }
+
+    #[test]
+    fn parse_timestamps() {
+        let timestamp = Timestamp::parse("<2026-10-18 Sun 10:00-11:30 +1w -3d>").unwrap();
+        assert!(timestamp.active);
+        assert_eq!(timestamp.date, date(2026, 10, 18));
+        assert_eq!(timestamp.time, Time::new(10, 0));
+        assert_eq!(
+            timestamp.end,
+            Some(RangeEnd::Time(Time::new(11, 30).unwrap()))
+        );
+        assert_eq!(
+            timestamp.repeater,
+            Some(Repeater {
+                kind: RepeaterKind::Cumulate,
+                interval: Interval {
+                    value: 1,
+                    unit: Unit::Week
+                }
+            })
+        );
+        assert_eq!(
+            timestamp.delay,
+            Some(Delay {
+                kind: DelayKind::All,
+                interval: Interval {
+                    value: 3,
+                    unit: Unit::Day
+                }
+            })
+        );
+
+        let timestamp = Timestamp::parse("[2026-10-18 Sun 9:05]").unwrap();
+        assert!(!timestamp.active);
+        assert_eq!(timestamp.time, Time::new(9, 5));
+        assert!(Time::parse("24:00").is_some());
+        assert!(Time::parse("24:59").is_none());
+        assert!(Timestamp::parse("<2026-10-18 Sun 23:00-24:30>").is_none());
+
+        let timestamp = Timestamp::parse("<2026-10-18 Sun>--<2026-10-20 Tue>").unwrap();
+        assert_eq!(
+            timestamp.end,
+            Some(RangeEnd::Date(date(2026, 10, 20), None))
+        );
+
+        let repeater = Timestamp::parse("<2026-10-18 ++1d>")
+            .unwrap()
+            .repeater
+            .unwrap();
+        assert_eq!(repeater.kind, RepeaterKind::CatchUp);
+        let repeater = Timestamp::parse("<2026-10-18 .+2m>")
+            .unwrap()
+            .repeater
+            .unwrap();
+        assert_eq!(repeater.kind, RepeaterKind::Restart);
+        let delay = Timestamp::parse("<2026-10-18 --3d>")
+            .unwrap()
+            .delay
+            .unwrap();
+        assert_eq!(delay.kind, DelayKind::First);
+
+        assert_eq!(Timestamp::parse("<2026-13-18 Sun>"), None);
+        assert_eq!(Timestamp::parse("<2026-10-18 Sun>]"), None);
+        assert_eq!(Timestamp::parse("<not a timestamp>"), None);
+        assert_eq!(Timestamp::parse("[2026-10-18 Sun>"), None);
+    }
+
+    #[test]
+    fn display_canonical() {
+        let cases = [
+            ("<2026-10-18 Sun>", "<2026-10-18 Sun>"),
+            ("<2026-10-18>", "<2026-10-18 Sun>"),
+            ("[2026-10-18 Mon 9:00]", "[2026-10-18 Sun 09:00]"),
+            (
+                "<2026-10-18 Sun 10:00-11:30 .+1w --2d>",
+                "<2026-10-18 Sun 10:00-11:30 .+1w --2d>",
+            ),
+            (
+                "[2026-10-18 Sun 10:00]--[2026-10-19 Mon 12:00]",
+                "[2026-10-18 Sun 10:00]--[2026-10-19 Mon 12:00]",
+            ),
+        ];
+        for (input, expected) in cases.iter() {
+            let timestamp = Timestamp::parse(input).unwrap();
+            assert_eq!(&format!("{}", timestamp), expected);
+        }
+    }
+
+    #[test]
+    fn find_in_text() {
+        let text = "meet [[link]] at <2026-10-18 Sun 10:00> or [2026-10-19 Mon]--[2026-10-20 Tue].";
+        let found: Vec<_> = find_timestamps(text).collect();
+
+        assert_eq!(found.len(), 2);
+        assert_eq!(&text[found[0].0.clone()], "<2026-10-18 Sun 10:00>");
+        assert_eq!(
+            &text[found[1].0.clone()],
+            "[2026-10-19 Mon]--[2026-10-20 Tue]"
+        );
+    }
+
+    #[test]
+    fn repeated_occurrences() {
+        let timestamp = Timestamp::parse("<2026-01-05 Mon +1w>").unwrap();
+        let dates: Vec<_> = timestamp
+            .occurrences(date(2026, 10, 18), date(2026, 11, 2))
+            .collect();
+        assert_eq!(
+            dates,
+            vec![date(2026, 10, 19), date(2026, 10, 26), date(2026, 11, 2)]
+        );
+
+        // months are added to the original date so short months do not drift
+        let timestamp = Timestamp::parse("<2020-01-31 Fri +1m>").unwrap();
+        let dates: Vec<_> = timestamp
+            .occurrences(date(2026, 4, 1), date(2026, 5, 31))
+            .collect();
+        assert_eq!(dates, vec![date(2026, 4, 30), date(2026, 5, 31)]);
+
+        let timestamp = Timestamp::parse("<2026-10-20 Tue>").unwrap();
+        assert_eq!(
+            timestamp
+                .occurrences(date(2026, 10, 18), date(2026, 10, 24))
+                .count(),
+            1
+        );
+        assert_eq!(
+            timestamp
+                .occurrences(date(2026, 10, 21), date(2026, 10, 24))
+                .count(),
+            0
+        );
+    }
+}